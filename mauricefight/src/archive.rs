@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// First bytes of an archive file.
+pub const MAGIC: &[u8; 4] = b"MFPK";
+pub const VERSION: u32 = 1;
+/// Longest entry name accepted when reading an archive.
+const MAX_NAME_LENGTH: u32 = 4096;
+/// Bytes of an index entry besides its name : name length, offset and size.
+const ENTRY_HEADER_SIZE: u64 = 4 + 8 + 8;
+
+/// Name of an archive entry : the relative path with '/' separators.
+pub fn entry_name(path: &str) -> String {
+    let name = path.replace('\\', "/");
+    name.trim_start_matches("./").to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    offset: u64,
+    size: u64,
+}
+
+/// Packed assets, read on demand. The file starts with the magic, the version and
+/// the number of entries, followed by the index (name length, name, offset, size)
+/// and the data of the entries.
+pub struct Archive {
+    path: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Archive {
+    /// Reads the index of an archive. The lengths read are checked against the
+    /// file size, a corrupt archive is reported without allocating for them.
+    pub fn open(path: &Path) -> io::Result<Archive> {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid(format!("{} is not an asset archive", path.display())));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported archive version {}", version)));
+        }
+        let count = read_u32(&mut reader)?;
+        if count as u64 * ENTRY_HEADER_SIZE > file_size {
+            return Err(invalid(format!("{} entries announced in a file of {} bytes", count, file_size)));
+        }
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let length = read_u32(&mut reader)?;
+            if length > MAX_NAME_LENGTH {
+                return Err(invalid(format!("entry name of {} bytes", length)));
+            }
+            let mut name = vec![0; length as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|e| invalid(e.to_string()))?;
+            let offset = read_u64(&mut reader)?;
+            let size = read_u64(&mut reader)?;
+            if offset.checked_add(size).is_none_or(|end| end > file_size) {
+                return Err(invalid(format!("entry {} outside of the archive", name)));
+            }
+            entries.insert(name, Entry { offset, size });
+        }
+        Ok(Archive {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&entry_name(name))
+    }
+
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self.entries.get(&entry_name(name)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("{} not found in the archive", name))
+        })?;
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.size as usize];
+        file.read_exact(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Writes an archive of `files`, given as entry name and file on disk.
+/// Used by the mauricefight-pack tool only.
+pub fn write(output: &Path, files: &[(String, PathBuf)]) -> io::Result<()> {
+    let mut sizes = Vec::new();
+    for (_, path) in files {
+        sizes.push(path.metadata()?.len());
+    }
+    let index_size: usize = files.iter().map(|(name, _)| 4 + name.len() + 8 + 8).sum();
+    let mut offset = (MAGIC.len() + 4 + 4 + index_size) as u64;
+    let mut writer = BufWriter::new(File::create(output)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(files.len() as u32).to_le_bytes())?;
+    for ((name, _), size) in files.iter().zip(&sizes) {
+        writer.write_all(&(name.len() as u32).to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&size.to_le_bytes())?;
+        offset += size;
+    }
+    for ((_, path), size) in files.iter().zip(&sizes) {
+        io::copy(&mut File::open(path)?.take(*size), &mut writer)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    /// File of the temporary directory, unique to the test.
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("mauricefight-{}-{}", std::process::id(), name))
+    }
+
+    fn header(version: u32, count: u32) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(&count.to_le_bytes());
+        data
+    }
+
+    fn entry(data: &mut Vec<u8>, name: &str, offset: u64, size: u64) {
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&size.to_le_bytes());
+    }
+
+    /// Error message of opening an archive made of `data`.
+    fn open_error(name: &str, data: &[u8]) -> String {
+        let path = temp_file(name);
+        fs::write(&path, data).unwrap();
+        let result = Archive::open(&path);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Ok(_) => panic!("{} opened", name),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn entry_names_use_slashes() {
+        assert_eq!(entry_name("./resources\\audio\\hit.wav"), "resources/audio/hit.wav");
+        assert_eq!(entry_name("resources/fonts.png"), "resources/fonts.png");
+    }
+
+    #[test]
+    fn reads_the_written_entries() {
+        let first = temp_file("first.txt");
+        let second = temp_file("second.txt");
+        let output = temp_file("round-trip.pak");
+        fs::write(&first, "maurice").unwrap();
+        fs::write(&second, "").unwrap();
+        let files = vec![
+            (String::from("resources/first.txt"), first.clone()),
+            (String::from("second.txt"), second.clone()),
+        ];
+        write(&output, &files).unwrap();
+        let archive = Archive::open(&output).unwrap();
+        assert!(archive.contains("./resources\\first.txt"));
+        assert_eq!(archive.read("resources/first.txt").unwrap(), b"maurice");
+        assert_eq!(archive.read("second.txt").unwrap(), b"");
+        assert_eq!(archive.read("third.txt").unwrap_err().kind(), io::ErrorKind::NotFound);
+        for path in [first, second, output] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_other_files_and_versions() {
+        assert!(open_error("magic.pak", b"PK\x03\x04\0\0\0\0\0\0\0\0").contains("not an asset archive"));
+        assert!(open_error("version.pak", &header(VERSION + 1, 0)).contains("unsupported archive version"));
+        let path = temp_file("short.pak");
+        fs::write(&path, b"MF").unwrap();
+        assert_eq!(Archive::open(&path).err().map(|e| e.kind()), Some(io::ErrorKind::UnexpectedEof));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_lengths_beyond_the_file() {
+        assert!(open_error("count.pak", &header(VERSION, u32::MAX)).contains("entries announced"));
+
+        let mut data = header(VERSION, 1);
+        data.extend_from_slice(&(MAX_NAME_LENGTH + 1).to_le_bytes());
+        data.resize(data.len() + ENTRY_HEADER_SIZE as usize, 0);
+        assert!(open_error("name.pak", &data).contains("entry name of"));
+
+        let mut data = header(VERSION, 1);
+        entry(&mut data, "hit.wav", 0, 1 << 20);
+        assert!(open_error("size.pak", &data).contains("outside of the archive"));
+
+        let mut data = header(VERSION, 1);
+        entry(&mut data, "hit.wav", u64::MAX, 2);
+        assert!(open_error("offset.pak", &data).contains("outside of the archive"));
+    }
+}
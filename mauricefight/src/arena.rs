@@ -1,14 +1,26 @@
-use sfml::graphics::Sprite;
+use crate::configuration::resources::{self, GameResources};
+use crate::sprites::textured_sprite::TexturedSprite;
 use sfml::graphics::RenderWindow;
 use sfml::graphics::RenderTarget;
 
-pub struct Arena<'a> {
-    sprite: Sprite<'a>,
+pub struct Arena {
+    sprite: TexturedSprite,
+    /// index of the level shown
+    pub level: usize,
 }
 
-impl<'a> Arena<'a> {
-    pub fn new(sprite: Sprite<'a>) -> Self {
-        Arena { sprite }
+impl Arena {
+    pub fn new(resources: &GameResources, level: usize) -> Self {
+        let name = &resources.configuration.levels[level].name;
+        Arena {
+            sprite: resources.get_sprite(&resources::level_texture(name)),
+            level,
+        }
+    }
+
+    /// Texture id of the level shown.
+    pub fn texture(&self, resources: &GameResources) -> String {
+        resources::level_texture(&resources.configuration.levels[self.level].name)
     }
 
     pub fn draw(&mut self, window: &mut RenderWindow) {
@@ -0,0 +1,127 @@
+use mauricefight::archive::Archive;
+use crate::configuration::error::ConfigurationError;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the packed archive searched in the asset roots.
+pub const ARCHIVE_NAME: &str = "mauricefight.pak";
+
+enum AssetSource {
+    Directory(PathBuf),
+    Archive(Archive),
+}
+
+/// Game files, read from an asset directory or from a packed archive.
+/// Paths are the relative ones written in the configuration.
+pub struct Assets {
+    source: AssetSource,
+}
+
+/// Directories searched for the assets : next to the executable, in the
+/// installation share directory, in the XDG data directories, then the current directory.
+fn candidate_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Some(dir) = env::current_exe().ok().and_then(|x| x.parent().map(Path::to_path_buf)) {
+        roots.push(dir.join("../share/mauricefight"));
+        roots.insert(0, dir);
+    }
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    roots.extend(data_home.map(|dir| dir.join("mauricefight")));
+    let data_dirs = env::var("XDG_DATA_DIRS").unwrap_or_else(|_| String::from("/usr/local/share:/usr/share"));
+    roots.extend(data_dirs.split(':').filter(|x| !x.is_empty()).map(|dir| Path::new(dir).join("mauricefight")));
+    roots.push(PathBuf::new());
+    roots
+}
+
+impl Assets {
+    pub fn directory(root: PathBuf) -> Assets {
+        Assets {
+            source: AssetSource::Directory(root),
+        }
+    }
+
+    /// Files relative to the current directory.
+    pub fn current_directory() -> Assets {
+        Assets::directory(PathBuf::new())
+    }
+
+    pub fn archive(path: &Path) -> Result<Assets, ConfigurationError> {
+        let archive = Archive::open(path).map_err(|error| ConfigurationError::Io {
+            path: path.display().to_string(),
+            error,
+        })?;
+        Ok(Assets {
+            source: AssetSource::Archive(archive),
+        })
+    }
+
+    /// Opens the archive given, or the root given, or the first candidate root
+    /// holding an archive or the configuration `manifest`.
+    pub fn locate(root: Option<&Path>, archive: Option<&Path>, manifest: &str) -> Result<Assets, ConfigurationError> {
+        if let Some(archive) = archive {
+            return Assets::archive(archive);
+        }
+        let roots = match root {
+            Some(root) => vec![root.to_path_buf()],
+            None => candidate_roots(),
+        };
+        for root in &roots {
+            let archive = root.join(ARCHIVE_NAME);
+            if archive.is_file() {
+                return Assets::archive(&archive);
+            }
+            if root.join(manifest).is_file() {
+                return Ok(Assets::directory(root.clone()));
+            }
+        }
+        let searched: Vec<String> = roots.iter().map(|x| x.display().to_string()).collect();
+        Err(ConfigurationError::Io {
+            path: manifest.to_string(),
+            error: io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no assets found, searched in {}", searched.join(", ")),
+            ),
+        })
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        match &self.source {
+            AssetSource::Directory(root) => fs::read(root.join(path)),
+            AssetSource::Archive(archive) => archive.read(path),
+        }
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        match &self.source {
+            AssetSource::Directory(root) => root.join(path).is_file(),
+            AssetSource::Archive(archive) => archive.contains(path),
+        }
+    }
+
+    /// Location of a file on disk, None for the archived files.
+    pub fn file_path(&self, path: &str) -> Option<PathBuf> {
+        match &self.source {
+            AssetSource::Directory(root) => Some(root.join(path)),
+            AssetSource::Archive(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Assets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.source {
+            AssetSource::Directory(root) if root.as_os_str().is_empty() => write!(f, "current directory"),
+            AssetSource::Directory(root) => write!(f, "directory {}", root.display()),
+            AssetSource::Archive(archive) => write!(f, "archive {}", archive.path().display()),
+        }
+    }
+}
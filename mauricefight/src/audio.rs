@@ -0,0 +1,133 @@
+use crate::configuration::resources::{GameResources, MusicHandle, SoundHandle};
+use crate::configuration::settings::Settings;
+use sfml::audio::{Music, Sound, SoundBuffer, SoundSource, SoundStatus};
+
+pub const SOUND_MENU_MOVE: &str = "menu_move";
+pub const SOUND_MENU_CONFIRM: &str = "menu_confirm";
+pub const SOUND_HIT: &str = "hit";
+pub const SOUND_BLOCK: &str = "block";
+pub const SOUND_KO: &str = "ko";
+
+/// Number of sound effects playing at the same time.
+const NB_VOICES: usize = 8;
+
+/// Sound playing a buffer of the resources, kept alive as long as it is set.
+struct Voice {
+    // declared before `buffer`, so that it is dropped first
+    sound: Sound<'static>,
+    buffer: Option<SoundHandle>,
+}
+
+impl Voice {
+    fn play(&mut self, buffer: SoundHandle, volume: f32) {
+        self.sound.stop();
+        // SAFETY: the buffer is on the heap behind the handle, which the voice
+        // keeps until the sound plays another buffer or is dropped.
+        let buffer_ref: &'static SoundBuffer = unsafe { &*(&**buffer as *const SoundBuffer) };
+        self.sound.set_buffer(buffer_ref);
+        self.buffer = Some(buffer);
+        self.sound.set_volume(volume);
+        self.sound.play();
+    }
+}
+
+/// Music streamed from a file read in memory, kept alive with it.
+struct MusicStream {
+    name: String,
+    // declared before `data`, so that it is dropped first
+    music: Music<'static>,
+    _data: MusicHandle,
+}
+
+/// Plays the sound effects and music declared in the `audio` configuration,
+/// loaded from the game resources.
+pub struct Audio {
+    voices: Vec<Voice>,
+    next_voice: usize,
+    music: Option<MusicStream>,
+    sfx_volume: f32,
+    music_volume: f32,
+}
+
+impl Audio {
+    pub fn new(settings: &Settings) -> Self {
+        let mut audio = Audio {
+            voices: (0..NB_VOICES)
+                .map(|_| Voice {
+                    sound: Sound::new(),
+                    buffer: None,
+                })
+                .collect(),
+            next_voice: 0,
+            music: None,
+            sfx_volume: 100.,
+            music_volume: 100.,
+        };
+        audio.set_volumes(settings);
+        audio
+    }
+
+    /// Applies master, music and sound effects volumes from the settings.
+    pub fn set_volumes(&mut self, settings: &Settings) {
+        let master = settings.volume as f32 / 100.;
+        self.sfx_volume = master * settings.sfx_volume as f32;
+        self.music_volume = master * settings.music_volume as f32;
+        for voice in self.voices.iter_mut() {
+            voice.sound.set_volume(self.sfx_volume);
+        }
+        if let Some(stream) = self.music.as_mut() {
+            stream.music.set_volume(self.music_volume);
+        }
+    }
+
+    /// Plays a sound effect on the next free voice, or on the oldest one.
+    /// Unknown or missing sounds are ignored.
+    pub fn play(&mut self, resources: &GameResources, name: &str) {
+        let buffer = match resources.sound(name) {
+            Some(buffer) => buffer,
+            None => return,
+        };
+        let index = (0..NB_VOICES)
+            .map(|i| (self.next_voice + i) % NB_VOICES)
+            .find(|i| self.voices[*i].sound.status() != SoundStatus::PLAYING)
+            .unwrap_or(self.next_voice);
+        self.voices[index].play(buffer, self.sfx_volume);
+        self.next_voice = (index + 1) % NB_VOICES;
+    }
+
+    /// Loops the music `name`, keeps it playing when it is already the current one.
+    /// `None` stops the music, its file is freed.
+    pub fn play_music(&mut self, resources: &GameResources, name: Option<&str>) {
+        if self.music.as_ref().map(|x| x.name.as_str()) == name {
+            return;
+        }
+        self.music = None;
+        let name = match name {
+            Some(name) => name,
+            None => return,
+        };
+        let data = match resources.music(name) {
+            Ok(data) => data,
+            Err(e) => {
+                log::warn!("{}", e);
+                return;
+            }
+        };
+        // SAFETY: the file data is on the heap behind the handle, which the stream
+        // keeps and drops after the music.
+        let data_ref: &'static [u8] = unsafe { &*(&*data as *const [u8]) };
+        match Music::from_memory(data_ref) {
+            Some(mut music) => {
+                music.set_looping(true);
+                music.set_volume(self.music_volume);
+                music.play();
+                self.music = Some(MusicStream {
+                    name: name.to_string(),
+                    music,
+                    _data: data,
+                });
+            }
+            None => log::warn!("unable to decode music {}", name),
+        }
+    }
+}
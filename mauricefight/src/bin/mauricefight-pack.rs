@@ -0,0 +1,67 @@
+//! Packs the game resources in one archive, read by `mauricefight --archive`
+//! or found as `mauricefight.pak` in the asset directories.
+
+use clap::Parser;
+use mauricefight::archive;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Packs the mauricefight resources in an archive")]
+struct Options {
+    /// archive file to write
+    #[arg(short, long, value_name = "FILE", default_value = "mauricefight.pak")]
+    output: PathBuf,
+
+    /// directory the entry names are relative to, the game asset root
+    #[arg(short = 'C', long, value_name = "DIR", default_value = ".")]
+    root: PathBuf,
+
+    /// files and directories to pack, relative to the root
+    #[arg(value_name = "PATH", default_value = "resources")]
+    inputs: Vec<PathBuf>,
+}
+
+/// Adds the files under `path`, sorted to write the same archive from the same files.
+fn collect(root: &Path, path: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    let full_path = root.join(path);
+    if full_path.is_dir() {
+        let mut children: Vec<PathBuf> = fs::read_dir(&full_path)?
+            .map(|entry| entry.map(|x| path.join(x.file_name())))
+            .collect::<io::Result<_>>()?;
+        children.sort();
+        for child in children {
+            collect(root, &child, files)?;
+        }
+    } else if full_path.is_file() {
+        files.push((archive::entry_name(&path.to_string_lossy()), full_path));
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", full_path.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn run(options: &Options) -> io::Result<usize> {
+    let mut files = Vec::new();
+    for input in &options.inputs {
+        collect(&options.root, input, &mut files)?;
+    }
+    archive::write(&options.output, &files)?;
+    Ok(files.len())
+}
+
+fn main() {
+    let options = Options::parse();
+    match run(&options) {
+        Ok(count) => println!("{} file(s) packed in {}", count, options.output.display()),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
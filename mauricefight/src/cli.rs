@@ -0,0 +1,151 @@
+use clap::{ArgAction, Parser, ValueEnum};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Screen shown when the game starts.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum StartMode {
+    /// title menu
+    Menu,
+    /// two fighters in the arena
+    Versus,
+    /// a fighter against a dummy
+    Training,
+}
+
+/// Command line options of the game.
+#[derive(Parser, Debug)]
+#[command(name = "mauricefight", version, about = "Maurice Fight, 2d fighting game")]
+pub struct Options {
+    /// directory the resource paths are relative to, searched by default next to
+    /// the executable, in the XDG data directories, then in the current directory
+    #[arg(short, long, value_name = "DIR")]
+    pub root: Option<PathBuf>,
+
+    /// asset archive built by mauricefight-pack, replacing the resource directory
+    #[arg(short, long, value_name = "FILE", conflicts_with = "root")]
+    pub archive: Option<PathBuf>,
+
+    /// configuration manifest, in json, ron or toml
+    #[arg(short, long, value_name = "FILE", default_value = "resources/configuration.json")]
+    pub config: String,
+
+    /// window size, overriding the settings
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    pub size: Option<(u32, u32)>,
+
+    /// starts in fullscreen
+    #[arg(long, conflicts_with = "windowed")]
+    pub fullscreen: bool,
+
+    /// starts in a window
+    #[arg(long)]
+    pub windowed: bool,
+
+    /// screen shown at start
+    #[arg(short, long, value_enum, default_value_t = StartMode::Menu)]
+    pub mode: StartMode,
+
+    /// character of the first player, the first character by default
+    #[arg(long = "p1", value_name = "CHARACTER")]
+    pub player1: Option<String>,
+
+    /// character of the second player, the first player one by default
+    #[arg(long = "p2", value_name = "CHARACTER")]
+    pub player2: Option<String>,
+
+    /// level name, the first level by default
+    #[arg(short, long, value_name = "LEVEL")]
+    pub stage: Option<String>,
+
+    /// plays the inputs recorded in a replay file
+    #[arg(long, value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<String>,
+
+    /// records the inputs of the game in a replay file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<String>,
+
+    /// speed of the simulation, like 0.5 for slow motion
+    #[arg(long, value_name = "SPEED", default_value_t = 1., value_parser = parse_speed)]
+    pub speed: f32,
+
+    /// hosts an online versus on this UDP port, as the first player
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["join", "replay", "record"])]
+    pub host: Option<u16>,
+
+    /// joins the online versus hosted at this address, as the second player
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["replay", "record"])]
+    pub join: Option<SocketAddr>,
+
+    /// ticks the local inputs are delayed by online, hiding as much latency
+    #[arg(long, value_name = "TICKS", default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=10))]
+    pub input_delay: u32,
+
+    /// latency added to the packets sent online, to test on one computer
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub net_latency: u64,
+
+    /// percentage of the packets sent online which are dropped, to test on one computer
+    #[arg(long, value_name = "PERCENT", default_value_t = 0., value_parser = parse_loss)]
+    pub net_loss: f32,
+
+    /// loads and checks the configuration and the resources without opening a window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// log levels per module, like "warn,fighter=debug,menu=trace", overriding the settings
+    #[arg(long, value_name = "SPEC")]
+    pub log: Option<String>,
+
+    /// copies the log messages to a file
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<String>,
+
+    /// more messages, repeat for more details
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// error messages only
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl Options {
+    /// Fullscreen requested by the options, the settings apply when None.
+    pub fn fullscreen(&self) -> Option<bool> {
+        match (self.fullscreen, self.windowed) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Online versus requested by the options.
+    pub fn is_online(&self) -> bool {
+        self.host.is_some() || self.join.is_some()
+    }
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let error = || format!("expected WIDTHxHEIGHT, found {}", value);
+    let (width, height) = value.split_once('x').ok_or_else(error)?;
+    match (width.trim().parse(), height.trim().parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(error()),
+    }
+}
+
+fn parse_speed(value: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(speed) if speed > 0. && speed.is_finite() => Ok(speed),
+        _ => Err(format!("expected a positive number, found {}", value)),
+    }
+}
+
+fn parse_loss(value: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(loss) if (0. ..=100.).contains(&loss) => Ok(loss),
+        _ => Err(format!("expected a percentage from 0 to 100, found {}", value)),
+    }
+}
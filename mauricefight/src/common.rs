@@ -1,10 +1,10 @@
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use sfml::window::Event;
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ResultEvent {
-    Exit,
     Solo,
-    Menu
+    Menu,
 }
 
 pub trait InputProcessor {
@@ -12,7 +12,7 @@ pub trait InputProcessor {
 }
 
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Left,
     Right,
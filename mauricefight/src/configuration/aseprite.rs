@@ -0,0 +1,82 @@
+use crate::assets::Assets;
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::loader;
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_derive::Deserialize;
+use std::fmt;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteFrame {
+    pub frame: AsepriteRect,
+    /// position of the trimmed frame in the untrimmed one
+    #[serde(rename = "spriteSourceSize")]
+    pub sprite_source_size: Option<AsepriteRect>,
+    pub duration: i32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteMeta {
+    #[serde(rename = "frameTags", default)]
+    pub frame_tags: Vec<AsepriteTag>,
+}
+
+/// Aseprite JSON export, in "hash" or "array" frames layout.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsepriteSheet {
+    #[serde(deserialize_with = "deserialize_frames")]
+    pub frames: Vec<AsepriteFrame>,
+    pub meta: AsepriteMeta,
+}
+
+impl AsepriteSheet {
+    pub fn load(assets: &Assets, path: &str) -> Result<AsepriteSheet, ConfigurationError> {
+        loader::read(assets, path)
+    }
+}
+
+/// Reads the frames as a list, keeping the file order of the "hash" layout
+/// (frame names are not sortable : "walk 10" comes after "walk 9").
+fn deserialize_frames<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<AsepriteFrame>, D::Error> {
+    struct FramesVisitor;
+
+    impl<'de> Visitor<'de> for FramesVisitor {
+        type Value = Vec<AsepriteFrame>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a list or a map of frames")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some(frame) = seq.next_element()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some((_, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+    }
+
+    deserializer.deserialize_any(FramesVisitor)
+}
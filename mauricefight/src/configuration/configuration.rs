@@ -1,6 +1,15 @@
+use crate::assets::Assets;
+use crate::configuration::aseprite::{AsepriteSheet, AsepriteTag};
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::inheritance;
+use crate::configuration::loader;
+use crate::configuration::validation::validate;
 use serde_derive::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Deserialize,Debug,Clone)]
+/// Collision box of a frame, relative to the pivot in sheet pixels.
+#[derive(Deserialize,Debug,Clone,Default)]
+#[serde(default)]
 pub struct HitBoxConfiguration {
     pub top : f32,
     pub left : f32,
@@ -8,36 +17,242 @@ pub struct HitBoxConfiguration {
     pub width : f32,
 }
 
-#[derive(Deserialize,Debug,Clone)]
+/// Event raised when the animation reaches a frame.
+#[derive(Deserialize,Debug,Clone,PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnimationEvent {
+    /// the attack hitbox becomes active
+    HitboxStart,
+    /// the attack hitbox is no longer active
+    HitboxEnd,
+    Sound { name : String },
+    /// speed added to the fighter, `x` pointing forward
+    Impulse {
+        #[serde(default)]
+        x : f32,
+        #[serde(default)]
+        y : f32,
+    },
+    /// the rest of the action can be cancelled by a new one
+    Cancel,
+}
+
+/// Frame of a sequence, all the fields are optional. The loader defaults `count`
+/// to the frame position and the hitboxes to the ones of the previous frame.
+#[derive(Deserialize,Debug,Clone,Default)]
+#[serde(default)]
 pub struct FrameConfiguration {
+    /// frame of the sprite sheet shown, from the sequence index
     pub count : i32,
+    /// hold duration in milliseconds, the sequence delay when 0
+    pub duration : i32,
+    pub events : Vec<AnimationEvent>,
     pub body : HitBoxConfiguration,
     pub head : HitBoxConfiguration,
     pub legs : HitBoxConfiguration,
+    /// attack area, active between the `hitbox_start` and `hitbox_end` events
+    pub hit : Option<HitBoxConfiguration>,
+    /// area the other fighters can't enter
+    pub push : Option<HitBoxConfiguration>,
 }
 
 #[derive(Deserialize,Debug,Clone)]
 pub struct SpriteSequenceConfiguration {
     pub index : i32,
     pub nb_frames : i32,
+    #[serde(default = "SpriteSequenceConfiguration::default_delay")]
     pub delay : i32,
+    #[serde(default)]
     pub speed : f32,
+    #[serde(default)]
     pub frames :  Vec<FrameConfiguration>,
 }
 
+impl SpriteSequenceConfiguration {
+    fn default_delay() -> i32 {
+        150
+    }
+
+    /// Follows an Aseprite tag : one frame per tagged sheet frame, the declared frames
+    /// keeping their boxes and events, each lasting as long as the sheet frame it shows.
+    /// The frames added keep the boxes of the previous one, like the declared frames.
+    fn import_tag(&mut self, tag : &AsepriteTag, sheet : &AsepriteSheet) {
+        self.index = tag.from as i32;
+        self.nb_frames = tag.to as i32 - self.index + 1;
+        self.frames.truncate(self.nb_frames as usize);
+        for count in self.frames.len() as i32..self.nb_frames {
+            let frame = match self.frames.last() {
+                Some(previous) => FrameConfiguration {
+                    count,
+                    body : previous.body.clone(),
+                    head : previous.head.clone(),
+                    legs : previous.legs.clone(),
+                    hit : previous.hit.clone(),
+                    push : previous.push.clone(),
+                    ..FrameConfiguration::default()
+                },
+                None => FrameConfiguration { count, ..FrameConfiguration::default() },
+            };
+            self.frames.push(frame);
+        }
+        for frame in self.frames.iter_mut() {
+            if let Some(sheet_frame) = sheet.frames.get((self.index + frame.count) as usize) {
+                frame.duration = sheet_frame.duration;
+            }
+        }
+    }
+}
+
 #[derive(Deserialize,Debug,Clone)]
+#[serde(default)]
 pub struct DisplayConfiguration {
     pub scale : f32,
     pub x_origin : f32,
     pub y_origin : f32,
 }
 
+impl Default for DisplayConfiguration {
+    fn default() -> Self {
+        DisplayConfiguration {
+            scale : 1.,
+            x_origin : 0.,
+            y_origin : 0.,
+        }
+    }
+}
+
+/// Area of a frame in a sprite sheet, with its pivot relative to the area
+/// and its display duration (0 when the sequence delay applies).
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SheetFrame {
+    pub left : i32,
+    pub top : i32,
+    pub width : i32,
+    pub height : i32,
+    pub x_origin : f32,
+    pub y_origin : f32,
+    pub duration : i32,
+}
+
+/// Frame of a packed atlas, the origin defaults to the sprite display origin.
+#[derive(Deserialize,Debug,Clone)]
+pub struct AtlasFrameConfiguration {
+    pub left : i32,
+    pub top : i32,
+    pub width : i32,
+    pub height : i32,
+    pub x_origin : Option<f32>,
+    pub y_origin : Option<f32>,
+}
+
 #[derive(Deserialize,Debug,Clone)]
 pub struct SpriteConfiguration {
     pub img : String,
     pub nb_frames : i32,
     pub size : i32,
+    #[serde(default)]
     pub display : DisplayConfiguration,
+    /// smooths the texture when scaled
+    #[serde(default)]
+    pub smooth : bool,
+    /// repeats the texture outside of its area, for tiled backgrounds
+    #[serde(default)]
+    pub repeated : bool,
+    /// cell width, `size` by default
+    pub width : Option<i32>,
+    /// cell height, `size` by default
+    pub height : Option<i32>,
+    /// cells per row of a grid, all the cells are on one row by default
+    pub columns : Option<i32>,
+    /// explicit frame areas of a packed atlas, replacing the cells
+    #[serde(default)]
+    pub atlas : Vec<AtlasFrameConfiguration>,
+    /// Aseprite JSON export describing the frames of `img`
+    pub aseprite : Option<String>,
+    /// Aseprite tag name -> action name, tags are used as action names by default
+    #[serde(default)]
+    pub tags : HashMap<String, String>,
+    /// frames read from the Aseprite export
+    #[serde(skip)]
+    pub frames : Vec<SheetFrame>,
+}
+
+impl SpriteConfiguration {
+    pub fn cell_width(&self) -> i32 {
+        self.width.unwrap_or(self.size)
+    }
+
+    pub fn cell_height(&self) -> i32 {
+        self.height.unwrap_or(self.size)
+    }
+
+    /// Frames of the sheet : the imported ones, the atlas ones, or the cells of the grid.
+    pub fn sheet_frames(&self) -> Vec<SheetFrame> {
+        if !self.frames.is_empty() {
+            return self.frames.clone();
+        }
+        let display = &self.display;
+        if !self.atlas.is_empty() {
+            return self
+                .atlas
+                .iter()
+                .map(|x| SheetFrame {
+                    left : x.left,
+                    top : x.top,
+                    width : x.width,
+                    height : x.height,
+                    x_origin : x.x_origin.unwrap_or(display.x_origin),
+                    y_origin : x.y_origin.unwrap_or(display.y_origin),
+                    duration : 0,
+                })
+                .collect();
+        }
+        let (width, height) = (self.cell_width(), self.cell_height());
+        let columns = self.columns.unwrap_or(self.nb_frames).max(1);
+        (0..self.nb_frames)
+            .map(|index| SheetFrame {
+                left : (index % columns) * width,
+                top : (index / columns) * height,
+                width,
+                height,
+                x_origin : display.x_origin,
+                y_origin : display.y_origin,
+                duration : 0,
+            })
+            .collect()
+    }
+
+    /// Width and height of the sheet area used by the frames.
+    pub fn sheet_size(&self) -> (i32, i32) {
+        self.sheet_frames().iter().fold((0, 0), |(width, height), frame| {
+            (width.max(frame.left + frame.width), height.max(frame.top + frame.height))
+        })
+    }
+
+    /// Reads the frames of the Aseprite export, if any.
+    fn import_aseprite(&mut self, assets : &Assets) -> Result<Option<AsepriteSheet>, ConfigurationError> {
+        let sheet = match &self.aseprite {
+            Some(path) => AsepriteSheet::load(assets, path)?,
+            None => return Ok(None),
+        };
+        // trimmed frames are moved by Aseprite, the pivot follows them
+        let display = &self.display;
+        self.frames = sheet
+            .frames
+            .iter()
+            .map(|x| SheetFrame {
+                left : x.frame.x,
+                top : x.frame.y,
+                width : x.frame.w,
+                height : x.frame.h,
+                x_origin : display.x_origin - x.sprite_source_size.as_ref().map_or(0., |r| r.x as f32),
+                y_origin : display.y_origin - x.sprite_source_size.as_ref().map_or(0., |r| r.y as f32),
+                duration : x.duration,
+            })
+            .collect();
+        self.nb_frames = self.frames.len() as i32;
+        Ok(Some(sheet))
+    }
 }
 
 #[derive(Deserialize,Debug,Clone)]
@@ -46,27 +261,172 @@ pub struct CursorConfiguration {
     pub delay : i32,
 }
 
+/// Glyph layout of a bitmap font sheet. Sizes are in sheet pixels.
+#[derive(Deserialize,Debug,Clone)]
+pub struct FontDescriptor {
+    /// characters in the order of the sprite sheet cells
+    #[serde(default = "FontDescriptor::default_glyphs")]
+    pub glyphs : String,
+    /// horizontal advance of a glyph without specific width
+    pub advance : Option<f32>,
+    /// advance of a space or of a character missing from the sheet
+    pub space : Option<f32>,
+    pub line_height : Option<f32>,
+    #[serde(default)]
+    pub widths : HashMap<char, f32>,
+}
+
+impl FontDescriptor {
+    fn default_glyphs() -> String {
+        String::from("abcdefghijklmnopqrstuvwxyz")
+    }
+}
+
+impl Default for FontDescriptor {
+    fn default() -> Self {
+        FontDescriptor {
+            glyphs : FontDescriptor::default_glyphs(),
+            advance : None,
+            space : None,
+            line_height : None,
+            widths : HashMap::new(),
+        }
+    }
+}
+
 #[derive(Deserialize,Debug,Clone)]
 pub struct FontsConfiguration {
     pub sprite : SpriteConfiguration,
+    /// inline glyph layout
+    pub font : Option<FontDescriptor>,
+    /// path of a font descriptor file, takes precedence over `font`
+    pub descriptor : Option<String>,
+}
+
+impl FontsConfiguration {
+    /// Replaces the inline glyph layout by the descriptor file one.
+    fn load_descriptor(&mut self, assets : &Assets) -> Result<(), ConfigurationError> {
+        if let Some(path) = &self.descriptor {
+            self.font = Some(loader::read(assets, path)?);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize,Debug,Clone,Copy,PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuAction {
+    Solo,
+    /// fight against a dummy, `argument` is the selected character
+    Training,
+    Multi,
+    Resume,
+    Title,
+    Quit,
+}
+
+#[derive(Deserialize,Debug,Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MenuItemKind {
+    /// triggers a game action, `argument` is e.g. the selected character
+    Action { action : MenuAction, argument : Option<String> },
+    /// opens the menu with the given id
+    Submenu { menu : String },
+    /// boolean setting
+    Toggle { setting : String },
+    /// setting cycling through a list of values
+    Choice { setting : String },
+    /// numeric setting drawn as a bar
+    Slider { setting : String },
+    /// returns to the previous menu
+    Back,
+}
+
+#[derive(Deserialize,Debug,Clone)]
+pub struct MenuItemConfiguration {
+    pub label : String,
+    #[serde(flatten)]
+    pub kind : MenuItemKind,
+}
+
+#[derive(Deserialize,Debug,Clone)]
+pub struct MenuConfiguration {
+    pub id : String,
+    pub title : Option<String>,
+    /// draws the menu background texture, disabled for menus shown over the game
+    #[serde(default = "MenuConfiguration::default_background")]
+    pub background : bool,
+    #[serde(default = "MenuConfiguration::default_x")]
+    pub x : f32,
+    #[serde(default = "MenuConfiguration::default_y")]
+    pub y : f32,
+    #[serde(default = "MenuConfiguration::default_step")]
+    pub step : f32,
+    pub items : Vec<MenuItemConfiguration>,
+}
+
+impl MenuConfiguration {
+    fn default_background() -> bool {
+        true
+    }
+
+    fn default_x() -> f32 {
+        100.
+    }
+
+    fn default_y() -> f32 {
+        50.
+    }
+
+    fn default_step() -> f32 {
+        30.
+    }
 }
 
 #[derive(Deserialize,Debug, Clone)]
 pub struct GuiConfiguration {
     pub cursor : CursorConfiguration,
     pub fonts : FontsConfiguration,
+    pub menus : Vec<MenuConfiguration>,
 }
 
 #[derive(Deserialize,Debug,Clone)]
 pub struct LevelConfiguration {
     pub name : String,
     pub sprite : SpriteConfiguration,
+    /// name of the music looped during the fight
+    pub music : Option<String>,
+}
+
+/// Sound effects and music files by name. Effects are referenced by the actions
+/// (`sound`), the menus play `menu_move` and `menu_confirm` and the fighters `hit`,
+/// `block` and `ko`, the effects not declared are silent. Music is referenced by
+/// the levels and `menu_music`.
+#[derive(Deserialize,Debug,Clone,Default)]
+pub struct AudioConfiguration {
+    #[serde(default)]
+    pub sounds : HashMap<String, String>,
+    #[serde(default)]
+    pub music : HashMap<String, String>,
+    /// music looped in the menus
+    pub menu_music : Option<String>,
 }
 
 #[derive(Deserialize,Debug,Clone)]
 pub struct ActionConfiguration {
     pub name : String,
     pub sequence : SpriteSequenceConfiguration,
+    /// sound effect played when the action starts
+    pub sound : Option<String>,
+    /// damage dealt when the hitbox touches
+    #[serde(default)]
+    pub damage : i32,
+    /// frames, at 60 per second, the opponent is stunned when hit
+    #[serde(default)]
+    pub hitstun : i32,
+    /// frames the opponent is stunned when blocking the hit
+    #[serde(default)]
+    pub blockstun : i32,
 }
 
 impl std::fmt::Display for ActionConfiguration {
@@ -83,8 +443,43 @@ pub struct CharacterConfiguration {
 }
 
 impl CharacterConfiguration {
+    /// Imports the Aseprite export of the sprite : each tag sets the frames of
+    /// the action of the same name, creating the action when it isn't declared.
+    fn import_aseprite(&mut self, assets : &Assets) -> Result<(), ConfigurationError> {
+        let sheet = match self.sprite.import_aseprite(assets)? {
+            Some(sheet) => sheet,
+            None => return Ok(()),
+        };
+        for tag in &sheet.meta.frame_tags {
+            let name = self.sprite.tags.get(&tag.name).unwrap_or(&tag.name).clone();
+            match self.actions.iter_mut().find(|x| x.name.eq_ignore_ascii_case(&name)) {
+                Some(action) => action.sequence.import_tag(tag, &sheet),
+                None => {
+                    let delay = sheet.frames.get(tag.from).map_or(100, |x| x.duration);
+                    let mut sequence = SpriteSequenceConfiguration {
+                        index : 0,
+                        nb_frames : 0,
+                        delay,
+                        speed : 0.,
+                        frames : Vec::new(),
+                    };
+                    sequence.import_tag(tag, &sheet);
+                    self.actions.push(ActionConfiguration {
+                        name,
+                        sequence,
+                        sound : None,
+                        damage : 0,
+                        hitstun : 0,
+                        blockstun : 0,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_action_configuration(&self, name : &str) -> Option<&ActionConfiguration> {
-        println!("search {}", name);
+        log::trace!("search {}", name);
         self.actions.iter().find(|x| x.name.eq_ignore_ascii_case(name))
     }
 }
@@ -100,11 +495,112 @@ pub struct Configuration {
     pub characters : Vec<CharacterConfiguration>,
     pub gui : GuiConfiguration,
     pub textures : TexturesConfiguration,
+    /// other sprites by id : portraits, HUD, effects
+    #[serde(default)]
+    pub sprites : HashMap<String, SpriteConfiguration>,
+    #[serde(default)]
+    pub audio : AudioConfiguration,
+    /// configuration files read, the manifest first
+    #[serde(skip)]
+    pub sources : Vec<String>,
 }
 
 impl Configuration {
+    /// Reads and validates the configuration manifest, in json, ron or toml,
+    /// with the level and character files it references, then resolves the inheritance.
+    pub fn load(assets : &Assets, path : &str) -> Result<Configuration, ConfigurationError> {
+        let mut sources = Vec::new();
+        let mut document = loader::read_manifest(assets, path, &mut sources)?;
+        inheritance::resolve(&mut document)?;
+        let mut configuration : Configuration = serde_json::from_value(document).map_err(|error| {
+            ConfigurationError::Parse {
+                path : path.to_string(),
+                error : error.to_string(),
+            }
+        })?;
+        configuration.sources = sources;
+        for character in configuration.characters.iter_mut() {
+            character.import_aseprite(assets)?;
+        }
+        let problems = validate(&configuration, assets);
+        if !problems.is_empty() {
+            return Err(ConfigurationError::Invalid(problems));
+        }
+        configuration.gui.fonts.load_descriptor(assets)?;
+        Ok(configuration)
+    }
+
+    /// Files referenced by the configuration, watched for hot reload.
+    pub fn files(&self) -> Vec<String> {
+        let mut files = self.sources.clone();
+        files.extend(self.levels.iter().map(|x| x.sprite.img.clone()));
+        files.extend(self.characters.iter().map(|x| x.sprite.img.clone()));
+        files.extend(self.characters.iter().filter_map(|x| x.sprite.aseprite.clone()));
+        files.push(self.gui.cursor.sprite.img.clone());
+        files.push(self.gui.fonts.sprite.img.clone());
+        files.extend(self.gui.fonts.descriptor.clone());
+        files.push(self.textures.sprite.img.clone());
+        files.extend(self.sprites.values().map(|x| x.img.clone()));
+        files.extend(self.audio.sounds.values().cloned());
+        files.extend(self.audio.music.values().cloned());
+        files
+    }
+
     pub fn get_character(&self, name : &str) -> Option<&CharacterConfiguration> {
-        println!("search {}", name);
+        log::trace!("search {}", name);
         self.characters.iter().find(|x| x.name.eq_ignore_ascii_case(name))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shipped_sheet() -> AsepriteSheet {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/sprite_maurice.json");
+        let data = std::fs::read_to_string(path).unwrap();
+        serde_json::from_str(&data).unwrap()
+    }
+
+    fn tag<'a>(sheet : &'a AsepriteSheet, name : &str) -> &'a AsepriteTag {
+        sheet.meta.frame_tags.iter().find(|x| x.name == name).unwrap()
+    }
+
+    #[test]
+    fn parses_the_shipped_export() {
+        let sheet = shipped_sheet();
+        assert_eq!(sheet.frames.len(), 38);
+        assert_eq!(sheet.frames[0].frame.w, 100);
+        let ko = tag(&sheet, "ko");
+        assert_eq!((ko.from, ko.to), (21, 23));
+        let kick = tag(&sheet, "HKick");
+        assert_eq!((kick.from, kick.to), (24, 28));
+    }
+
+    #[test]
+    fn imported_frames_keep_the_boxes_of_the_previous_one() {
+        let sheet = shipped_sheet();
+        let body = HitBoxConfiguration { top : -40., left : -10., height : 40., width : 20. };
+        let mut sequence = SpriteSequenceConfiguration {
+            index : 0,
+            nb_frames : 1,
+            delay : SpriteSequenceConfiguration::default_delay(),
+            speed : 0.,
+            frames : vec![FrameConfiguration {
+                body : body.clone(),
+                hit : Some(body.clone()),
+                events : vec![AnimationEvent::HitboxStart],
+                ..FrameConfiguration::default()
+            }],
+        };
+        sequence.import_tag(tag(&sheet, "HKick"), &sheet);
+        assert_eq!((sequence.index, sequence.nb_frames), (24, 5));
+        assert_eq!(sequence.frames.len(), 5);
+        for (count, frame) in sequence.frames.iter().enumerate() {
+            assert_eq!(frame.count, count as i32);
+            assert_eq!(frame.body.top, body.top);
+            assert_eq!(frame.hit.as_ref().map(|x| x.width), Some(body.width));
+            assert_eq!(frame.duration, sheet.frames[24 + count].duration);
+        }
+        assert!(sequence.frames[1].events.is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::io;
+
+/// A problem found in the configuration, located by its JSON path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationProblem {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} : {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigurationError {
+    /// the configuration file can't be read
+    Io { path: String, error: io::Error },
+    /// the configuration file isn't a valid configuration document
+    Parse { path: String, error: String },
+    /// the configuration is well formed but inconsistent
+    Invalid(Vec<ValidationProblem>),
+    /// an image declared in the configuration can't be loaded as a texture
+    Texture { path: String },
+    /// a sound or music declared in the configuration is missing or can't be decoded
+    Sound { path: String },
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigurationError::Io { path, error } => {
+                write!(f, "unable to read {} : {}", path, error)
+            }
+            ConfigurationError::Parse { path, error } => {
+                write!(f, "invalid configuration {} : {}", path, error)
+            }
+            ConfigurationError::Invalid(problems) => {
+                write!(f, "{} configuration problem(s) :", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  {}", problem)?;
+                }
+                Ok(())
+            }
+            ConfigurationError::Texture { path } => write!(f, "unable to load texture {}", path),
+            ConfigurationError::Sound { path } => write!(f, "unable to load sound {}", path),
+        }
+    }
+}
+
+impl std::error::Error for ConfigurationError {}
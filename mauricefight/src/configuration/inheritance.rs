@@ -0,0 +1,266 @@
+use crate::configuration::error::{ConfigurationError, ValidationProblem};
+use serde_json::{Map, Value};
+
+/// Field naming the entry an entry inherits from.
+const EXTENDS: &str = "extends";
+/// Hitboxes of a frame, inherited from the previous frame.
+const BOXES: [&str; 5] = ["body", "head", "legs", "hit", "push"];
+
+fn problem(path: &str, message: String) -> ValidationProblem {
+    ValidationProblem {
+        path: path.to_string(),
+        message,
+    }
+}
+
+fn name_of(value: &Value) -> Option<&str> {
+    value.get("name").and_then(Value::as_str)
+}
+
+fn find<'v>(pool: &'v [Value], name: &str) -> Option<&'v Value> {
+    pool.iter()
+        .find(|x| name_of(x).is_some_and(|x| x.eq_ignore_ascii_case(name)))
+}
+
+/// Copies the fields of `base` missing in `value`, merging the objects found in both.
+fn merge(value: &mut Value, base: &Value) {
+    if let (Value::Object(object), Value::Object(base)) = (value, base) {
+        for (key, base_value) in base {
+            match object.get_mut(key) {
+                Some(value) => merge(value, base_value),
+                None => {
+                    object.insert(key.clone(), base_value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Merges a character with its base, the actions being merged by name.
+fn merge_character(value: &mut Value, base: &Value) {
+    let base_actions = base.get("actions").and_then(Value::as_array).cloned().unwrap_or_default();
+    let mut base = base.clone();
+    if let Some(base) = base.as_object_mut() {
+        base.remove("actions");
+    }
+    merge(value, &base);
+    let object = match value.as_object_mut() {
+        Some(object) => object,
+        None => return,
+    };
+    let actions = object
+        .entry("actions")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if let Value::Array(actions) = actions {
+        for base_action in base_actions {
+            let name = name_of(&base_action).unwrap_or_default().to_string();
+            match actions
+                .iter_mut()
+                .find(|x| name_of(x).is_some_and(|x| x.eq_ignore_ascii_case(&name)))
+            {
+                Some(action) => merge(action, &base_action),
+                None => actions.push(base_action),
+            }
+        }
+    }
+}
+
+/// Entry with the fields inherited along its `extends` chain, searched in `pool`.
+fn inherit(
+    entry: &Value,
+    pool: &[Value],
+    path: &str,
+    chain: &mut Vec<String>,
+    merge: fn(&mut Value, &Value),
+) -> Result<Value, ValidationProblem> {
+    let mut value = entry.clone();
+    let base_name = match value.as_object_mut().and_then(|x| x.remove(EXTENDS)) {
+        Some(Value::String(name)) => name,
+        Some(_) => return Err(problem(path, String::from("extends must be a name"))),
+        None => return Ok(value),
+    };
+    if chain.iter().any(|x| x.eq_ignore_ascii_case(&base_name)) {
+        return Err(problem(path, format!("\"{}\" inherits from itself", base_name)));
+    }
+    let base = find(pool, &base_name)
+        .ok_or_else(|| problem(path, format!("unknown base \"{}\"", base_name)))?;
+    chain.push(base_name);
+    let base = inherit(base, pool, path, chain, merge)?;
+    merge(&mut value, &base);
+    Ok(value)
+}
+
+/// Resolves the inheritance of the entries of `list`, their bases being searched in `pool`.
+fn inherit_all(
+    list: &mut [Value],
+    pool: &[Value],
+    path: &str,
+    merge: fn(&mut Value, &Value),
+    problems: &mut Vec<ValidationProblem>,
+) {
+    for (i, entry) in list.iter_mut().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        let mut chain: Vec<String> = name_of(entry).map(String::from).into_iter().collect();
+        match inherit(entry, pool, &path, &mut chain, merge) {
+            Ok(value) => *entry = value,
+            Err(problem) => problems.push(problem),
+        }
+    }
+}
+
+/// Completes the frames of a sequence up to `nb_frames` : `count` defaults to the
+/// frame position and the hitboxes missing in a frame are the ones of the previous frame.
+fn complete_frames(sequence: &mut Map<String, Value>) {
+    let nb_frames = sequence.get("nb_frames").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let frames = sequence
+        .entry("frames")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if let Value::Array(frames) = frames {
+        while frames.len() < nb_frames {
+            frames.push(Value::Object(Map::new()));
+        }
+        let mut previous: Option<Map<String, Value>> = None;
+        for (i, frame) in frames.iter_mut().enumerate() {
+            if let Value::Object(frame) = frame {
+                frame.entry("count").or_insert_with(|| Value::from(i));
+                if let Some(previous) = &previous {
+                    for name in BOXES {
+                        if let (false, Some(hitbox)) = (frame.contains_key(name), previous.get(name)) {
+                            frame.insert(name.to_string(), hitbox.clone());
+                        }
+                    }
+                }
+                previous = Some(frame.clone());
+            }
+        }
+    }
+}
+
+/// Resolves the `extends` of the characters, from the `templates` and the other
+/// characters, then the `extends` of their actions and the frame defaults.
+pub fn resolve(document: &mut Value) -> Result<(), ConfigurationError> {
+    let mut problems = Vec::new();
+    let templates = document.get("templates").and_then(Value::as_array).cloned().unwrap_or_default();
+    if let Some(Value::Array(characters)) = document.get_mut("characters") {
+        let pool: Vec<Value> = templates.iter().chain(characters.iter()).cloned().collect();
+        inherit_all(characters, &pool, "characters", merge_character, &mut problems);
+        for (i, character) in characters.iter_mut().enumerate() {
+            if let Some(Value::Array(actions)) = character.get_mut("actions") {
+                let pool = actions.clone();
+                let path = format!("characters[{}].actions", i);
+                inherit_all(actions, &pool, &path, merge, &mut problems);
+                for action in actions.iter_mut() {
+                    if let Some(Value::Object(sequence)) = action.get_mut("sequence") {
+                        complete_frames(sequence);
+                    }
+                }
+            }
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigurationError::Invalid(problems))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn problems(document: &mut Value) -> Vec<ValidationProblem> {
+        match resolve(document) {
+            Err(ConfigurationError::Invalid(problems)) => problems,
+            result => panic!("invalid document expected, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn characters_inherit_templates_and_actions_by_name() {
+        let mut document = json!({
+            "templates" : [
+                { "name" : "base", "sprite" : { "img" : "base.png", "size" : 64 }, "actions" : [
+                    { "name" : "idle", "sequence" : { "index" : 0, "nb_frames" : 1 } },
+                    { "name" : "punch", "sequence" : { "index" : 4, "nb_frames" : 2 } }
+                ] }
+            ],
+            "characters" : [
+                { "name" : "Maurice", "extends" : "base", "sprite" : { "img" : "maurice.png" }, "actions" : [
+                    { "name" : "punch", "sequence" : { "index" : 8 } }
+                ] },
+                { "name" : "Jean", "extends" : "Maurice" }
+            ]
+        });
+        resolve(&mut document).unwrap();
+        let maurice = &document["characters"][0];
+        assert_eq!(maurice.get(EXTENDS), None);
+        assert_eq!(maurice["sprite"], json!({ "img" : "maurice.png", "size" : 64 }));
+        assert_eq!(maurice["actions"][0]["name"], "punch");
+        assert_eq!(maurice["actions"][0]["sequence"]["index"], 8);
+        assert_eq!(maurice["actions"][0]["sequence"]["nb_frames"], 2);
+        assert_eq!(maurice["actions"][1]["name"], "idle");
+        assert_eq!(document["characters"][1]["sprite"]["img"], "maurice.png");
+        assert_eq!(document["characters"][1]["actions"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn actions_inherit_the_other_actions() {
+        let mut document = json!({
+            "characters" : [
+                { "name" : "Maurice", "actions" : [
+                    { "name" : "crouch", "sound" : "swoosh", "sequence" : { "index" : 3, "nb_frames" : 1 } },
+                    { "name" : "ko", "extends" : "crouch" }
+                ] }
+            ]
+        });
+        resolve(&mut document).unwrap();
+        let ko = &document["characters"][0]["actions"][1];
+        assert_eq!(ko["name"], "ko");
+        assert_eq!(ko["sound"], "swoosh");
+        assert_eq!(ko["sequence"]["index"], 3);
+    }
+
+    #[test]
+    fn reports_unknown_and_recursive_bases() {
+        let mut document = json!({
+            "characters" : [
+                { "name" : "Maurice", "extends" : "Nobody" },
+                { "name" : "Jean", "extends" : "Paul" },
+                { "name" : "Paul", "extends" : "Jean" },
+                { "name" : "Pierre", "actions" : [ { "name" : "idle", "extends" : 3 } ] }
+            ]
+        });
+        let problems = problems(&mut document);
+        let paths: Vec<&str> = problems.iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["characters[0]", "characters[1]", "characters[2]", "characters[3].actions[0]"]
+        );
+        assert!(problems[0].message.contains("unknown base"));
+        assert!(problems[1].message.contains("inherits from itself"));
+    }
+
+    #[test]
+    fn frames_default_to_their_position_and_the_previous_boxes() {
+        let body = json!({ "top" : -40, "left" : -10, "height" : 40, "width" : 20 });
+        let hit = json!({ "top" : -30, "left" : 10, "height" : 10, "width" : 15 });
+        let mut sequence = json!({
+            "nb_frames" : 4,
+            "frames" : [
+                { "body" : body },
+                { "count" : 3, "hit" : hit },
+                { "body" : null }
+            ]
+        });
+        complete_frames(sequence.as_object_mut().unwrap());
+        let frames = &sequence["frames"];
+        assert_eq!(frames.as_array().map(Vec::len), Some(4));
+        let counts: Vec<&Value> = (0..4).map(|i| &frames[i]["count"]).collect();
+        assert_eq!(counts, [&json!(0), &json!(3), &json!(2), &json!(3)]);
+        assert_eq!(frames[1]["body"], body);
+        assert_eq!(frames[2]["hit"], hit);
+        assert_eq!(frames[2]["body"], Value::Null);
+        assert_eq!(frames[3]["hit"], hit);
+    }
+}
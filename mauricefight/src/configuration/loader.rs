@@ -0,0 +1,136 @@
+use crate::assets::Assets;
+use crate::configuration::error::ConfigurationError;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::Path;
+
+/// Sections of the root manifest whose entries may be file names.
+const SPLIT_SECTIONS: [&str; 3] = ["levels", "characters", "templates"];
+
+/// Data format of a configuration file, selected by its extension.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Ron,
+    Toml,
+}
+
+impl Format {
+    /// Json when the extension is unknown.
+    pub fn from_path(path: &str) -> Format {
+        match Path::new(path).extension().and_then(|x| x.to_str()) {
+            Some("ron") => Format::Ron,
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    pub fn is_data_file(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|x| x.to_str()),
+            Some("json") | Some("ron") | Some("toml")
+        )
+    }
+
+    fn parse<T: DeserializeOwned>(self, path: &str, text: &str) -> Result<T, ConfigurationError> {
+        let result = match self {
+            Format::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+            // through a ron value, serde_json values don't read the unnamed structs
+            Format::Ron => ron::from_str::<ron::Value>(text)
+                .map_err(|e| e.to_string())
+                .and_then(|value| value.into_rust().map_err(|e| e.to_string())),
+            Format::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+        };
+        result.map_err(|error| ConfigurationError::Parse {
+            path: path.to_string(),
+            error,
+        })
+    }
+}
+
+/// Reads a data file in the format of its extension.
+pub fn read<T: DeserializeOwned>(assets: &Assets, path: &str) -> Result<T, ConfigurationError> {
+    let text = assets.read_to_string(path).map_err(|error| ConfigurationError::Io {
+        path: path.to_string(),
+        error,
+    })?;
+    Format::from_path(path).parse(path, &text)
+}
+
+/// Reads the root manifest, replacing the file names found in `levels`,
+/// `characters` and `templates` by the content of these files, relative to the manifest.
+/// Every file read is added to `sources`.
+pub fn read_manifest(assets: &Assets, path: &str, sources: &mut Vec<String>) -> Result<Value, ConfigurationError> {
+    let mut root: Value = read(assets, path)?;
+    sources.push(path.to_string());
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    for section in SPLIT_SECTIONS {
+        if let Some(Value::Array(entries)) = root.get_mut(section) {
+            for entry in entries.iter_mut() {
+                if let Value::String(file) = entry {
+                    let file = directory.join(&*file).to_string_lossy().into_owned();
+                    *entry = read(assets, &file)?;
+                    sources.push(file);
+                }
+            }
+        }
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_derive::Deserialize;
+    use std::path::PathBuf;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Sample {
+        name: String,
+        frames: Vec<i32>,
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            name: String::from("maurice"),
+            frames: vec![1, 2],
+        }
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(Format::from_path("characters/maurice.ron"), Format::Ron);
+        assert_eq!(Format::from_path("configuration.toml"), Format::Toml);
+        assert_eq!(Format::from_path("configuration.json"), Format::Json);
+        assert_eq!(Format::from_path("configuration"), Format::Json);
+        assert!(Format::is_data_file(Path::new("resources/configuration.ron")));
+        assert!(!Format::is_data_file(Path::new("resources/fonts.png")));
+    }
+
+    #[test]
+    fn parses_every_format() {
+        let json: Sample = Format::Json.parse("a.json", r#"{ "name" : "maurice", "frames" : [1, 2] }"#).unwrap();
+        let ron: Sample = Format::Ron.parse("a.ron", r#"(name : "maurice", frames : [1, 2])"#).unwrap();
+        let toml: Sample = Format::Toml.parse("a.toml", "name = \"maurice\"\nframes = [1, 2]\n").unwrap();
+        assert_eq!(json, sample());
+        assert_eq!(ron, sample());
+        assert_eq!(toml, sample());
+    }
+
+    #[test]
+    fn parse_errors_name_the_file() {
+        match Format::Toml.parse::<Sample>("bad.toml", "name = ") {
+            Err(ConfigurationError::Parse { path, .. }) => assert_eq!(path, "bad.toml"),
+            result => panic!("parse error expected, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn manifest_reads_the_split_files() {
+        let assets = Assets::directory(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        let mut sources = Vec::new();
+        let manifest = read_manifest(&assets, "resources/configuration.json", &mut sources).unwrap();
+        assert_eq!(sources, ["resources/configuration.json", "resources/characters/maurice.json"]);
+        assert_eq!(manifest["characters"][0]["name"], "Maurice");
+    }
+}
@@ -1,98 +1,297 @@
-use crate::configuration::configuration::Configuration;
+use crate::assets::Assets;
+use crate::configuration::configuration::{Configuration, SpriteConfiguration};
+use crate::configuration::error::ConfigurationError;
+use crate::sprites::textured_sprite::TexturedSprite;
 use sfml::{
-    graphics::{Image, IntRect, Sprite, Texture, Transformable},
+    audio::SoundBuffer,
+    graphics::{Image, IntRect, Texture},
     SfBox,
 };
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
 
-pub struct GameResources<'a> {
+/// Texture shared by the store and the sprites drawing it. Replacing its content
+/// updates every sprite using it.
+pub type TextureHandle = Rc<RefCell<SfBox<Texture>>>;
+
+/// Sound effect shared by the store and the voices playing it. Reloading the sounds
+/// leaves the voices playing the previous buffers until they stop.
+pub type SoundHandle = Rc<SfBox<SoundBuffer>>;
+
+/// Encoded music file, kept by the music streaming it.
+pub type MusicHandle = Rc<[u8]>;
+
+pub const FONTS_TEXTURE: &str = "gui/fonts";
+pub const CURSOR_TEXTURE: &str = "gui/cursor";
+pub const BACKGROUND_TEXTURE: &str = "gui/background";
+
+pub fn character_texture(name: &str) -> String {
+    format!("characters/{}", name)
+}
+
+pub fn level_texture(name: &str) -> String {
+    format!("levels/{}", name)
+}
+
+/// Texture of an entry of the `sprites` section : portraits, HUD, effects...
+pub fn sprite_texture(id: &str) -> String {
+    format!("sprites/{}", id)
+}
+
+/// Image file of a texture with its loading options.
+#[derive(Debug, Clone)]
+struct TextureDeclaration {
+    img: String,
+    /// area of the image loaded, the whole image when empty
+    area: IntRect,
+    smooth: bool,
+    repeated: bool,
+}
+
+impl TextureDeclaration {
+    /// Area of a sprite sheet used by its frames.
+    fn sheet(sprite: &SpriteConfiguration) -> Self {
+        let (width, height) = sprite.sheet_size();
+        TextureDeclaration {
+            area: IntRect::new(0, 0, width, height),
+            ..TextureDeclaration::image(sprite)
+        }
+    }
+
+    /// Whole image, for the level backgrounds.
+    fn image(sprite: &SpriteConfiguration) -> Self {
+        TextureDeclaration {
+            img: sprite.img.clone(),
+            area: IntRect::default(),
+            smooth: sprite.smooth,
+            repeated: sprite.repeated,
+        }
+    }
+}
+
+/// Textures of a configuration by id.
+fn declarations(configuration: &Configuration) -> HashMap<String, TextureDeclaration> {
+    let mut declarations = HashMap::new();
+    for level in &configuration.levels {
+        declarations.insert(level_texture(&level.name), TextureDeclaration::image(&level.sprite));
+    }
+    for character in &configuration.characters {
+        declarations.insert(character_texture(&character.name), TextureDeclaration::sheet(&character.sprite));
+    }
+    for (id, sprite) in &configuration.sprites {
+        declarations.insert(sprite_texture(id), TextureDeclaration::sheet(sprite));
+    }
+    let gui = &configuration.gui;
+    declarations.insert(FONTS_TEXTURE.to_string(), TextureDeclaration::sheet(&gui.fonts.sprite));
+    declarations.insert(CURSOR_TEXTURE.to_string(), TextureDeclaration::sheet(&gui.cursor.sprite));
+    declarations.insert(BACKGROUND_TEXTURE.to_string(), TextureDeclaration::sheet(&configuration.textures.sprite));
+    declarations
+}
+
+/// Configuration, assets, textures and sounds of the game. The textures and sounds
+/// declared by the configuration are loaded on first use, then can be reloaded while
+/// the game runs, the sprites and the voices keep a handle on theirs.
+/// The music files are read when they start playing.
+pub struct GameResources {
     pub configuration: Configuration,
-    pub textures: &'a HashMap<String, SfBox<Texture>>,
-    pub sprites: HashMap<String, Sprite<'a>>,
+    /// shared with the loading threads
+    pub assets: Arc<Assets>,
+    declarations: HashMap<String, TextureDeclaration>,
+    textures: RefCell<HashMap<String, TextureHandle>>,
+    /// None for the sounds failing to load, not tried again until a reload
+    sounds: RefCell<HashMap<String, Option<SoundHandle>>>,
 }
 
-impl<'a> GameResources<'a> {
-    pub fn new(
-        configuration: Configuration,
-        textures: &'a HashMap<String, SfBox<Texture>>,
-    ) -> Self {
-        let nmap: HashMap<String, Sprite> = HashMap::new();
-        let mut res = GameResources {
+impl GameResources {
+    pub fn new(configuration: Configuration, assets: Assets) -> Self {
+        GameResources {
+            declarations: declarations(&configuration),
             configuration,
-            textures,
-            sprites: nmap,
+            assets: Arc::new(assets),
+            textures: RefCell::new(HashMap::new()),
+            sounds: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn declaration(&self, id: &str) -> Result<&TextureDeclaration, ConfigurationError> {
+        self.declarations.get(id).ok_or_else(|| texture_error(id))
+    }
+
+    fn load_texture(&self, id: &str) -> Result<SfBox<Texture>, ConfigurationError> {
+        let image = decode_image(&self.assets, &self.declaration(id)?.img)?;
+        self.create_texture(id, &image)
+    }
+
+    /// Creates the texture `id` from its decoded image, on the thread owning the window.
+    fn create_texture(&self, id: &str, image: &Image) -> Result<SfBox<Texture>, ConfigurationError> {
+        let declaration = self.declaration(id)?;
+        let path = declaration.img.as_str();
+        let mut texture = Texture::new().ok_or_else(|| texture_error(path))?;
+        texture
+            .load_from_image(image, declaration.area)
+            .map_err(|_| texture_error(path))?;
+        texture.set_smooth(declaration.smooth);
+        texture.set_repeated(declaration.repeated);
+        let size = texture.size();
+        log::debug!("texture {} loaded from {}, {}x{}", id, path, size.x, size.y);
+        Ok(texture)
+    }
+
+    /// Image files of the textures not loaded yet among `ids`, to decode them in advance.
+    pub fn pending_textures(&self, ids: &[String]) -> Vec<(String, String)> {
+        let textures = self.textures.borrow();
+        ids.iter()
+            .filter(|id| !textures.contains_key(*id))
+            .filter_map(|id| self.declarations.get(id).map(|x| (id.clone(), x.img.clone())))
+            .collect()
+    }
+
+    /// Stores the texture `id` created from an image decoded in advance.
+    pub fn upload_texture(&self, id: &str, image: &Image) -> Result<(), ConfigurationError> {
+        let texture = self.create_texture(id, image)?;
+        self.store(id, texture);
+        Ok(())
+    }
+
+    /// Replaces the content of the texture `id` for the sprites using it, or adds it.
+    fn store(&self, id: &str, texture: SfBox<Texture>) -> TextureHandle {
+        let existing = self.textures.borrow().get(id).cloned();
+        match existing {
+            Some(handle) => {
+                *handle.borrow_mut() = texture;
+                handle
+            }
+            None => {
+                let handle = Rc::new(RefCell::new(texture));
+                self.textures.borrow_mut().insert(id.to_string(), handle.clone());
+                handle
+            }
+        }
+    }
+
+    /// Texture `id`, loaded on first use. A texture failing to load is drawn
+    /// empty until a reload succeeds.
+    pub fn texture(&self, id: &str) -> TextureHandle {
+        if let Some(handle) = self.textures.borrow().get(id) {
+            return handle.clone();
+        }
+        let texture = match self.load_texture(id) {
+            Ok(texture) => texture,
+            Err(e) => {
+                log::error!("{}", e);
+                Texture::new().expect("unable to create an empty texture")
+            }
         };
-        for (key, texture) in res.textures {
-            let mut sprite = Sprite::new();
-            sprite.set_texture(&texture, true);
-            res.sprites.insert(key.clone(), sprite);
+        self.store(id, texture)
+    }
+
+    pub fn get_sprite(&self, id: &str) -> TexturedSprite {
+        TexturedSprite::new(self.texture(id))
+    }
+
+    /// Decodes the images of all the declared textures without creating them,
+    /// which needs no display. Returns the number of textures.
+    pub fn check_images(&self) -> Result<usize, ConfigurationError> {
+        let mut paths: Vec<&str> = self.declarations.values().map(|x| x.img.as_str()).collect();
+        paths.sort_unstable();
+        paths.dedup();
+        for path in paths {
+            let size = decode_image(&self.assets, path)?.size();
+            log::debug!("image {} decoded, {}x{}", path, size.x, size.y);
+        }
+        Ok(self.declarations.len())
+    }
+
+    /// Uses the textures declared by a reloaded configuration.
+    pub fn declare_textures(&mut self, configuration: &Configuration) {
+        self.declarations = declarations(configuration);
+    }
+
+    /// Loads the used textures again, the sprites show the new ones. A texture that
+    /// fails keeps its previous image, the failures are logged and returned.
+    pub fn reload_textures(&self) -> Vec<ConfigurationError> {
+        let mut errors = Vec::new();
+        for (id, handle) in self.textures.borrow().iter() {
+            match self.load_texture(id) {
+                Ok(texture) => *handle.borrow_mut() = texture,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    errors.push(e);
+                }
+            }
+        }
+        errors
+    }
+
+    /// Forgets a texture, it is freed when no sprite uses it anymore.
+    pub fn unload_texture(&self, id: &str) -> bool {
+        self.textures.borrow_mut().remove(id).is_some()
+    }
+
+    fn load_sound(&self, name: &str) -> Result<SoundHandle, ConfigurationError> {
+        let file = self.configuration.audio.sounds.get(name).ok_or_else(|| sound_error(name))?;
+        let data = read_asset(&self.assets, file)?;
+        let buffer = SoundBuffer::from_memory(&data).map_err(|_| sound_error(file))?;
+        log::debug!("sound {} loaded from {}", name, file);
+        Ok(Rc::new(buffer))
+    }
+
+    /// Sound effect `name`, loaded on first use. Unknown or missing sounds are silent.
+    pub fn sound(&self, name: &str) -> Option<SoundHandle> {
+        if let Some(sound) = self.sounds.borrow().get(name) {
+            return sound.clone();
         }
-        res
+        let sound = self
+            .load_sound(name)
+            .map_err(|e| log::warn!("{}", e))
+            .ok();
+        self.sounds.borrow_mut().insert(name.to_string(), sound.clone());
+        sound
     }
 
-    pub fn get_sprite(&self, name: &str) -> Sprite<'a> {
-        self.sprites.get(name).unwrap().clone()
+    /// Loads every declared sound effect, so that none is decoded during a fight.
+    /// Returns the number of sounds loaded.
+    pub fn load_sounds(&self) -> usize {
+        let names: Vec<&String> = self.configuration.audio.sounds.keys().collect();
+        names.into_iter().filter(|name| self.sound(name).is_some()).count()
+    }
+
+    /// Forgets the loaded sounds and loads the declared ones again, the voices
+    /// playing keep the previous buffers until they stop.
+    pub fn reload_sounds(&self) -> usize {
+        self.sounds.borrow_mut().clear();
+        self.load_sounds()
+    }
+
+    /// Reads the music file `name`, to be streamed by the music playing it.
+    pub fn music(&self, name: &str) -> Result<MusicHandle, ConfigurationError> {
+        let file = self.configuration.audio.music.get(name).ok_or_else(|| sound_error(name))?;
+        Ok(read_asset(&self.assets, file)?.into())
     }
 }
 
-pub fn load_textures(configuration: &Configuration) -> HashMap<String, SfBox<Texture>> {
-    let mut textures = HashMap::new();
-    for character in &configuration.characters {
-        let mut texture = Texture::new().unwrap();
-        texture
-            .load_from_file(
-                &character.sprite.img,
-                IntRect::new(
-                    0,
-                    0,
-                    character.sprite.size * character.sprite.nb_frames,
-                    character.sprite.size,
-                ),
-            )
-            .unwrap();
-        texture.set_smooth(true);
-        textures.insert(character.name.clone(), texture);
-    }
-    let mut texture_fonts = Texture::new().unwrap();
-    texture_fonts
-        .load_from_file(
-            &configuration.gui.fonts.sprite.img,
-            IntRect::new(
-                0,
-                0,
-                configuration.gui.fonts.sprite.size * configuration.gui.fonts.sprite.nb_frames,
-                configuration.gui.fonts.sprite.size,
-            ),
-        )
-        .unwrap();
-    textures.insert(String::from("fonts"), texture_fonts);
-    let mut texture_cursor = Texture::new().unwrap();
-    texture_cursor
-        .load_from_file(
-            &configuration.gui.cursor.sprite.img,
-            IntRect::new(
-                0,
-                0,
-                configuration.gui.cursor.sprite.size * configuration.gui.cursor.sprite.nb_frames,
-                configuration.gui.cursor.sprite.size,
-            ),
-        )
-        .unwrap();
-    textures.insert(String::from("cursor"), texture_cursor);
-
-    let background_menu = Image::from_file(configuration.textures.sprite.img.as_str()).unwrap();
-    let mut texture_menu = Texture::new().unwrap();
-    let menu_rect = IntRect::new(
-        0,
-        0,
-        configuration.textures.sprite.size,
-        configuration.textures.sprite.size,
-    );
-    texture_menu
-        .load_from_image(&background_menu, menu_rect)
-        .unwrap();
-    texture_menu.set_smooth(true);
-    texture_menu.set_repeated(true);
-    textures.insert(String::from("background"), texture_menu);
-    textures
+fn sound_error(name: &str) -> ConfigurationError {
+    ConfigurationError::Sound {
+        path: name.to_string(),
+    }
+}
+
+fn texture_error(path: &str) -> ConfigurationError {
+    ConfigurationError::Texture {
+        path: path.to_string(),
+    }
+}
+
+/// Reads and decodes an image, on any thread.
+pub fn decode_image(assets: &Assets, path: &str) -> Result<Image, ConfigurationError> {
+    Image::from_memory(&read_asset(assets, path)?).ok_or_else(|| texture_error(path))
+}
+
+fn read_asset(assets: &Assets, path: &str) -> Result<Vec<u8>, ConfigurationError> {
+    assets.read(path).map_err(|error| ConfigurationError::Io {
+        path: path.to_string(),
+        error,
+    })
 }
@@ -0,0 +1,236 @@
+use crate::screen::ScalingMode;
+use crate::training::DummyBehaviour;
+use serde_derive::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (640, 480),
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1920, 1080),
+];
+pub const FRAME_LIMITS: [u32; 4] = [0, 30, 60, 120];
+pub const AA_LEVELS: [u32; 4] = [0, 2, 4, 8];
+pub const LANGUAGES: [&str; 2] = ["fr", "en"];
+pub const ROUND_TIMES: [u32; 4] = [30, 60, 99, 0];
+pub const ROUND_COUNTS: [u32; 3] = [1, 3, 5];
+
+/// User preferences, stored apart from the game data in configuration.json.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub scaling: ScalingMode,
+    pub vsync: bool,
+    pub frame_limit: u32,
+    pub aa_level: u32,
+    pub volume: u32,
+    pub music_volume: u32,
+    pub sfx_volume: u32,
+    pub language: String,
+    pub round_time: u32,
+    pub round_count: u32,
+    /// what the dummy does in the training mode
+    pub dummy: DummyBehaviour,
+    /// shows the recent inputs of the fighters
+    pub input_display: bool,
+    /// log levels, like "warn,fighter=debug"
+    pub log: String,
+    /// file the log messages are copied to
+    pub log_file: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            width: 800,
+            height: 600,
+            fullscreen: false,
+            scaling: ScalingMode::Integer,
+            vsync: true,
+            frame_limit: 60,
+            aa_level: 0,
+            volume: 100,
+            music_volume: 80,
+            sfx_volume: 100,
+            language: String::from("fr"),
+            round_time: 99,
+            round_count: 3,
+            dummy: DummyBehaviour::Stand,
+            input_display: false,
+            log: String::from("info"),
+            log_file: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Location of the settings file : $XDG_CONFIG_HOME/mauricefight/settings.json,
+    /// falling back to ~/.config and finally to the current directory.
+    pub fn path() -> PathBuf {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        match config_dir {
+            Some(dir) => dir.join("mauricefight").join("settings.json"),
+            None => PathBuf::from("settings.json"),
+        }
+    }
+
+    /// Loads the user settings, defaults are used when the file is missing or unreadable.
+    pub fn load() -> Self {
+        let path = Settings::path();
+        match File::open(&path) {
+            Ok(file) => match serde_json::from_reader(file) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    // read before the logger is installed, it depends on the settings
+                    eprintln!("invalid settings file {} : {}", path.display(), e);
+                    Settings::default()
+                }
+            },
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Settings::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(&path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    /// Changes the setting named `key` (as in the settings file) to its next or previous value.
+    /// Returns false for an unknown setting.
+    pub fn change(&mut self, key: &str, forward: bool) -> bool {
+        match key {
+            "resolution" => self.next_resolution(forward),
+            "fullscreen" => self.fullscreen = !self.fullscreen,
+            "scaling" => self.next_scaling(),
+            "vsync" => self.vsync = !self.vsync,
+            "frame_limit" => self.next_frame_limit(forward),
+            "aa_level" => self.next_aa_level(forward),
+            "volume" => change_volume(&mut self.volume, forward),
+            "music_volume" => change_volume(&mut self.music_volume, forward),
+            "sfx_volume" => change_volume(&mut self.sfx_volume, forward),
+            "language" => self.next_language(forward),
+            "round_time" => self.next_round_time(forward),
+            "round_count" => self.next_round_count(forward),
+            "dummy" => self.next_dummy(forward),
+            "input_display" => self.input_display = !self.input_display,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Text displayed in menus for the setting named `key`.
+    pub fn value_label(&self, key: &str) -> Option<String> {
+        let yes_no = |value: bool| String::from(if value { "oui" } else { "non" });
+        let label = match key {
+            "resolution" => format!("{}x{}", self.width, self.height),
+            "fullscreen" => yes_no(self.fullscreen),
+            "scaling" => String::from(match self.scaling {
+                ScalingMode::Integer => "entière",
+                ScalingMode::Smooth => "lisse",
+            }),
+            "vsync" => yes_no(self.vsync),
+            "frame_limit" => match self.frame_limit {
+                0 => String::from("aucune"),
+                limit => limit.to_string(),
+            },
+            "aa_level" => match self.aa_level {
+                0 => String::from("non"),
+                level => format!("x{}", level),
+            },
+            "volume" => self.volume.to_string(),
+            "music_volume" => self.music_volume.to_string(),
+            "sfx_volume" => self.sfx_volume.to_string(),
+            "language" => self.language.clone(),
+            "round_time" => match self.round_time {
+                0 => String::from("infini"),
+                time => time.to_string(),
+            },
+            "round_count" => self.round_count.to_string(),
+            "dummy" => self.dummy.label().to_string(),
+            "input_display" => yes_no(self.input_display),
+            _ => return None,
+        };
+        Some(label)
+    }
+
+    /// Position between 0 and 1 of a numeric setting, used by menu sliders.
+    pub fn ratio(&self, key: &str) -> Option<f32> {
+        match key {
+            "volume" => Some(self.volume as f32 / 100.),
+            "music_volume" => Some(self.music_volume as f32 / 100.),
+            "sfx_volume" => Some(self.sfx_volume as f32 / 100.),
+            _ => None,
+        }
+    }
+
+    pub fn next_resolution(&mut self, forward: bool) {
+        let (width, height) = cycle(&RESOLUTIONS, &(self.width, self.height), forward);
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn next_scaling(&mut self) {
+        self.scaling = match self.scaling {
+            ScalingMode::Integer => ScalingMode::Smooth,
+            ScalingMode::Smooth => ScalingMode::Integer,
+        };
+    }
+
+    pub fn next_frame_limit(&mut self, forward: bool) {
+        self.frame_limit = cycle(&FRAME_LIMITS, &self.frame_limit, forward);
+    }
+
+    pub fn next_aa_level(&mut self, forward: bool) {
+        self.aa_level = cycle(&AA_LEVELS, &self.aa_level, forward);
+    }
+
+    pub fn next_language(&mut self, forward: bool) {
+        self.language = cycle(&LANGUAGES, &self.language.as_str(), forward).to_string();
+    }
+
+    pub fn next_round_time(&mut self, forward: bool) {
+        self.round_time = cycle(&ROUND_TIMES, &self.round_time, forward);
+    }
+
+    pub fn next_round_count(&mut self, forward: bool) {
+        self.round_count = cycle(&ROUND_COUNTS, &self.round_count, forward);
+    }
+
+    pub fn next_dummy(&mut self, forward: bool) {
+        self.dummy = cycle(&DummyBehaviour::ALL, &self.dummy, forward);
+    }
+}
+
+fn change_volume(volume: &mut u32, forward: bool) {
+    *volume = if forward {
+        (*volume + 10).min(100)
+    } else {
+        volume.saturating_sub(10)
+    };
+}
+
+/// Returns the value following (or preceding) `current` in `values`, wrapping around.
+/// An unknown current value restarts from the first entry.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: &T, forward: bool) -> T {
+    match values.iter().position(|v| v == current) {
+        Some(index) => {
+            let len = values.len();
+            let next = if forward { index + 1 } else { index + len - 1 };
+            values[next % len]
+        }
+        None => values[0],
+    }
+}
@@ -0,0 +1,264 @@
+use crate::assets::Assets;
+use crate::configuration::configuration::{
+    AnimationEvent, CharacterConfiguration, Configuration, MenuAction, MenuItemKind, SpriteConfiguration,
+};
+use crate::configuration::error::ValidationProblem;
+use crate::fighters::fighter_state::State;
+
+struct Validator<'a> {
+    assets: &'a Assets,
+    problems: Vec<ValidationProblem>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, path: String, message: String) {
+        self.problems.push(ValidationProblem { path, message });
+    }
+
+    fn check_file(&mut self, path: String, file: &str) {
+        if !self.assets.exists(file) {
+            self.report(path, format!("file not found : {}", file));
+        }
+    }
+
+    fn check_sprite(&mut self, path: String, sprite: &SpriteConfiguration) {
+        self.check_file(format!("{}.img", path), &sprite.img);
+        if sprite.nb_frames <= 0 {
+            self.report(format!("{}.nb_frames", path), format!("must be positive, found {}", sprite.nb_frames));
+        }
+        if sprite.size <= 0 && (sprite.width.is_none() || sprite.height.is_none()) {
+            self.report(format!("{}.size", path), format!("must be positive, found {}", sprite.size));
+        }
+        if let Some(columns) = sprite.columns {
+            if columns <= 0 {
+                self.report(format!("{}.columns", path), format!("must be positive, found {}", columns));
+            }
+        }
+        if !sprite.atlas.is_empty() && sprite.atlas.len() as i32 != sprite.nb_frames {
+            self.report(
+                format!("{}.atlas", path),
+                format!("{} frames declared but nb_frames is {}", sprite.atlas.len(), sprite.nb_frames),
+            );
+        }
+    }
+
+    fn check_character(&mut self, path: String, character: &CharacterConfiguration) {
+        self.check_sprite(format!("{}.sprite", path), &character.sprite);
+        for state in State::ALL {
+            let action = state.to_string();
+            if character.get_action_configuration(&action).is_none() {
+                self.report(
+                    format!("{}.actions", path),
+                    format!("missing action \"{}\" used by the {:?} state", action, state),
+                );
+            }
+        }
+        for (i, action) in character.actions.iter().enumerate() {
+            let path = format!("{}.actions[{}].sequence", path, i);
+            let sequence = &action.sequence;
+            if sequence.nb_frames <= 0 {
+                self.report(
+                    format!("{}.nb_frames", path),
+                    format!("must be positive, found {}", sequence.nb_frames),
+                );
+            }
+            if sequence.frames.len() as i32 != sequence.nb_frames {
+                self.report(
+                    format!("{}.frames", path),
+                    format!("{} frames declared but nb_frames is {}", sequence.frames.len(), sequence.nb_frames),
+                );
+            }
+            for (j, frame) in sequence.frames.iter().enumerate() {
+                if frame.count < 0 || sequence.index + frame.count >= character.sprite.nb_frames {
+                    self.report(
+                        format!("{}.frames[{}].count", path, j),
+                        format!("frame {} is outside the sprite sheet", sequence.index + frame.count),
+                    );
+                }
+            }
+            if sequence.index < 0 || sequence.index + sequence.nb_frames > character.sprite.nb_frames {
+                self.report(
+                    format!("{}.index", path),
+                    format!(
+                        "frames {}..{} exceed the {} frames of the sprite sheet",
+                        sequence.index,
+                        sequence.index + sequence.nb_frames,
+                        character.sprite.nb_frames
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_menus(&mut self, configuration: &Configuration) {
+        let menus = &configuration.gui.menus;
+        for id in ["title", "pause"] {
+            if !menus.iter().any(|menu| menu.id == id) {
+                self.report(String::from("gui.menus"), format!("missing menu \"{}\"", id));
+            }
+        }
+        for (i, menu) in menus.iter().enumerate() {
+            if menu.items.is_empty() {
+                self.report(format!("gui.menus[{}].items", i), String::from("a menu needs at least one item"));
+            }
+            for (j, item) in menu.items.iter().enumerate() {
+                let path = format!("gui.menus[{}].items[{}]", i, j);
+                match &item.kind {
+                    MenuItemKind::Submenu { menu } if !menus.iter().any(|x| &x.id == menu) => {
+                        self.report(path, format!("unknown menu \"{}\"", menu));
+                    }
+                    MenuItemKind::Action {
+                        action: MenuAction::Solo | MenuAction::Training,
+                        argument: Some(name),
+                    } if configuration.get_character(name).is_none() => {
+                        self.report(path, format!("unknown character \"{}\"", name));
+                    }
+                    MenuItemKind::Action {
+                        action: MenuAction::Solo | MenuAction::Training,
+                        argument: None,
+                    } => {
+                        self.report(path, String::from("the character is required as argument"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Checks that the music and the sound effects referenced by name are declared.
+    fn check_audio(&mut self, configuration: &Configuration) {
+        let audio = &configuration.audio;
+        for (name, file) in &audio.sounds {
+            self.check_file(format!("audio.sounds.{}", name), file);
+        }
+        for (name, file) in &audio.music {
+            self.check_file(format!("audio.music.{}", name), file);
+        }
+        let mut music = vec![(String::from("audio.menu_music"), &audio.menu_music)];
+        for (i, level) in configuration.levels.iter().enumerate() {
+            music.push((format!("levels[{}].music", i), &level.music));
+        }
+        for (path, name) in music {
+            if let Some(name) = name.as_ref().filter(|x| !audio.music.contains_key(*x)) {
+                self.report(path, format!("unknown music \"{}\"", name));
+            }
+        }
+        for (i, character) in configuration.characters.iter().enumerate() {
+            for (j, action) in character.actions.iter().enumerate() {
+                let path = format!("characters[{}].actions[{}]", i, j);
+                let events = action.sequence.frames.iter().flat_map(|x| &x.events);
+                let sounds = action.sound.iter().chain(events.filter_map(|x| match x {
+                    AnimationEvent::Sound { name } => Some(name),
+                    _ => None,
+                }));
+                for name in sounds.filter(|x| !audio.sounds.contains_key(*x)) {
+                    self.report(path.clone(), format!("unknown sound \"{}\"", name));
+                }
+            }
+        }
+    }
+}
+
+/// Checks the consistency of a configuration, reporting every problem found.
+pub fn validate(configuration: &Configuration, assets: &Assets) -> Vec<ValidationProblem> {
+    let mut validator = Validator {
+        assets,
+        problems: Vec::new(),
+    };
+    if configuration.levels.is_empty() {
+        validator.report(String::from("levels"), String::from("at least one level is required"));
+    }
+    for (i, level) in configuration.levels.iter().enumerate() {
+        validator.check_file(format!("levels[{}].sprite.img", i), &level.sprite.img);
+    }
+    if configuration.characters.is_empty() {
+        validator.report(String::from("characters"), String::from("at least one character is required"));
+    }
+    for (i, character) in configuration.characters.iter().enumerate() {
+        validator.check_character(format!("characters[{}]", i), character);
+    }
+    validator.check_sprite(String::from("gui.cursor.sprite"), &configuration.gui.cursor.sprite);
+    validator.check_sprite(String::from("gui.fonts.sprite"), &configuration.gui.fonts.sprite);
+    if let Some(descriptor) = &configuration.gui.fonts.descriptor {
+        validator.check_file(String::from("gui.fonts.descriptor"), descriptor);
+    }
+    validator.check_menus(configuration);
+    validator.check_sprite(String::from("textures.sprite"), &configuration.textures.sprite);
+    for (id, sprite) in &configuration.sprites {
+        validator.check_sprite(format!("sprites.{}", id), sprite);
+    }
+    validator.check_audio(configuration);
+    validator.problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn shipped() -> (Configuration, Assets) {
+        let assets = Assets::directory(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        let configuration = Configuration::load(&assets, "resources/configuration.json").unwrap();
+        (configuration, assets)
+    }
+
+    fn paths(configuration: &Configuration, assets: &Assets) -> Vec<String> {
+        validate(configuration, assets).into_iter().map(|x| x.path).collect()
+    }
+
+    #[test]
+    fn shipped_configuration_is_valid() {
+        let (configuration, assets) = shipped();
+        assert!(validate(&configuration, &assets).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_actions_and_frames() {
+        let (mut configuration, assets) = shipped();
+        let character = &mut configuration.characters[0];
+        character.actions.retain(|x| x.name != "ko");
+        character.actions[0].sequence.nb_frames += 1;
+        character.actions[1].sequence.frames[0].count = character.sprite.nb_frames;
+        let problems = validate(&configuration, &assets);
+        assert!(problems
+            .iter()
+            .any(|x| x.path == "characters[0].actions" && x.message.contains("\"ko\"")));
+        let paths: Vec<&str> = problems.iter().map(|x| x.path.as_str()).collect();
+        assert!(paths.contains(&"characters[0].actions[0].sequence.frames"));
+        assert!(paths.contains(&"characters[0].actions[1].sequence.frames[0].count"));
+    }
+
+    #[test]
+    fn reports_menu_problems() {
+        let (mut configuration, assets) = shipped();
+        configuration.gui.menus[0].items.clear();
+        configuration.gui.menus[1].items[0].kind = MenuItemKind::Action {
+            action: MenuAction::Solo,
+            argument: None,
+        };
+        configuration.gui.menus[2].items[0].kind = MenuItemKind::Action {
+            action: MenuAction::Training,
+            argument: Some(String::from("Nobody")),
+        };
+        configuration.gui.menus.retain(|x| x.id != "pause");
+        let paths = paths(&configuration, &assets);
+        assert!(paths.contains(&String::from("gui.menus[0].items")));
+        assert!(paths.contains(&String::from("gui.menus[1].items[0]")));
+        assert!(paths.contains(&String::from("gui.menus[2].items[0]")));
+        assert!(paths.contains(&String::from("gui.menus")));
+    }
+
+    #[test]
+    fn reports_unknown_audio_and_missing_files() {
+        let (mut configuration, assets) = shipped();
+        configuration.audio.sounds.insert(String::from("missing"), String::from("audio/missing.wav"));
+        configuration.audio.menu_music = Some(String::from("unknown"));
+        configuration.characters[0].actions[0].sound = Some(String::from("unknown"));
+        configuration.levels[0].sprite.img = String::from("resources/missing.png");
+        let paths = paths(&configuration, &assets);
+        assert!(paths.contains(&String::from("audio.sounds.missing")));
+        assert!(paths.contains(&String::from("audio.menu_music")));
+        assert!(paths.contains(&String::from("characters[0].actions[0]")));
+        assert!(paths.contains(&String::from("levels[0].sprite.img")));
+    }
+}
@@ -0,0 +1,60 @@
+use sfml::system::Clock;
+use sfml::SfBox;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Delay between two checks of the watched files.
+const POLL_DELAY: i32 = 500;
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Detects file modifications by polling their modification time.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    clock: SfBox<Clock>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let mut watcher = FileWatcher {
+            files: Vec::new(),
+            clock: Clock::start(),
+        };
+        watcher.watch(paths);
+        watcher
+    }
+
+    /// Replaces the watched files, their current state being the reference.
+    pub fn watch(&mut self, mut paths: Vec<PathBuf>) {
+        paths.sort();
+        paths.dedup();
+        self.files = paths
+            .into_iter()
+            .map(|path| {
+                let time = modified(&path);
+                (path, time)
+            })
+            .collect();
+    }
+
+    /// Files modified, created or removed since the previous poll.
+    /// Files are checked at most every POLL_DELAY milliseconds.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.clock.elapsed_time().as_milliseconds() < POLL_DELAY {
+            return Vec::new();
+        }
+        self.clock.restart();
+        let mut changed = Vec::new();
+        for (path, time) in self.files.iter_mut() {
+            let current = modified(path);
+            if current != *time {
+                *time = current;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
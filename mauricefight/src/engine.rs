@@ -1,102 +1,845 @@
 use crate::arena::Arena;
+use crate::assets::Assets;
+use crate::audio::Audio;
+use crate::cli::StartMode;
+use crate::common::Direction;
 use crate::configuration::resources::{self, GameResources};
 use crate::fighters::fighter::Fighter;
-use crate::gui::menu::Menu;
+use crate::configuration::settings::Settings;
+use crate::configuration::configuration::{Configuration, MenuAction};
+use crate::configuration::loader::Format;
+use crate::configuration::watcher::FileWatcher;
+use crate::gui::debug::DebugOverlay;
+use crate::gui::input_display::InputDisplay;
+use crate::gui::menu::{Cursor, Menu, MenuEvent};
+use crate::gui::text::{Align, BitmapFont};
 use crate::common::{InputProcessor, ResultEvent};
+use crate::loading::ImageLoader;
+use crate::netplay::{self, RollbackSession};
+use crate::replay::{ReplayPlayer, ReplayRecorder};
+use crate::save_state::{FightSnapshot, SAVE_STATE_FILE};
+use crate::screen::{Screen, GAME_HEIGHT, GAME_WIDTH};
+use crate::time_control::TimeControl;
+use crate::training::Training;
+use std::path::PathBuf;
 use sfml::SfBox;
 use sfml::{
-    graphics::{Color, RenderTarget, RenderWindow, View},
-    window::Event,
+    graphics::{Color, RectangleShape, RenderTarget, Shape, Transformable},
+    system::{Clock, Vector2f, Vector2i},
+    window::{Event, Key},
 };
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum DisplayState {
     Menu,
+    /// textures of the next fight being prepared
+    Loading,
     Game,
+    Pause,
 }
 
-pub struct MauriceFight2dEngine<'a> {
-    pub window: RenderWindow,
-    view: SfBox<View>,
-    arena: Arena<'a>,
-    fighters: Vec<Box<Fighter<'a>>>,
+const LOADING_BAR_WIDTH: f32 = 120.;
+const LOADING_BAR_HEIGHT: f32 = 6.;
+
+/// Position of a fighter at the start of a fight, from its index.
+fn start_position(index: usize) -> Vector2f {
+    Vector2f::new(120. + 60. * index as f32, 150.)
+}
+
+pub struct MauriceFight2dEngine {
+    pub screen: Screen,
+    /// background of the last fight
+    arena: Option<Arena>,
+    fighters: Vec<Fighter>,
     display: DisplayState,
-    menu: Menu<'a>,
+    menu: Menu,
+    audio: Audio,
+    resources: GameResources,
+    font: BitmapFont,
+    configuration_path: String,
+    watcher: FileWatcher,
+    /// error of the last reload or of a fight unable to start, displayed until
+    /// the next successful reload
+    reload_message: Option<String>,
+    /// index of the level fought in
+    level: usize,
+    /// inputs played instead of the keyboard ones
+    replay: Option<ReplayPlayer>,
+    /// inputs recorded with the file they are saved to
+    recorder: Option<(ReplayRecorder, String)>,
+    /// images decoded for the next fight, with its characters
+    loading: Option<(ImageLoader, Vec<String>)>,
+    loading_cursor: Cursor,
+    debug: DebugOverlay,
+    /// ticks run per frame, changed by the debug hotkeys
+    time_control: TimeControl,
+    /// time since the previous frame, the fight running at a fixed tick rate
+    frame_clock: SfBox<Clock>,
+    /// state saved by the quick save hotkey
+    save_state: Option<FightSnapshot>,
+    /// recent inputs of the fighters, shown when the setting is on
+    input_display: InputDisplay,
+    /// dummy and frame data of the training mode, None in the other fights
+    training: Option<Training>,
+    /// online versus, the fighters being driven by the inputs exchanged
+    online: Option<RollbackSession>,
 }
 
-impl<'a> MauriceFight2dEngine<'a> {
+/// Files on disk the configuration comes from, none when the assets are archived.
+fn watched_files(assets: &Assets, configuration_path: &str, configuration: &Configuration) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = assets.file_path(configuration_path).into_iter().collect();
+    files.extend(configuration.files().iter().filter_map(|file| assets.file_path(file)));
+    files
+}
+
+impl MauriceFight2dEngine {
     pub fn new(
-        window: RenderWindow,
-        view: SfBox<View>,
-        arena: Arena<'a>,
-        resources: &'a GameResources,
+        screen: Screen,
+        resources: GameResources,
+        audio: Audio,
+        settings: Settings,
+        configuration_path: &str,
     ) -> Self {
-        MauriceFight2dEngine {
-            window,
-            view,
-            arena,
+        let mut engine = MauriceFight2dEngine {
+            screen,
+            arena: None,
             fighters: Vec::new(),
             display: DisplayState::Menu,
-            menu: Menu::new(resources),
+            menu: Menu::new(&resources, settings, "title"),
+            audio,
+            font: BitmapFont::new(&resources),
+            loading_cursor: Cursor::new(&resources),
+            input_display: InputDisplay::new(&resources),
+            configuration_path: configuration_path.to_string(),
+            watcher: FileWatcher::new(watched_files(&resources.assets, configuration_path, &resources.configuration)),
+            resources,
+            reload_message: None,
+            level: 0,
+            replay: None,
+            recorder: None,
+            loading: None,
+            debug: DebugOverlay::new(),
+            time_control: TimeControl::new(1.),
+            frame_clock: Clock::start(),
+            save_state: None,
+            training: None,
+            online: None,
+        };
+        engine.play_menu_music();
+        engine
+    }
+
+    fn play_menu_music(&mut self) {
+        let music = self.resources.configuration.audio.menu_music.as_deref();
+        self.audio.play_music(&self.resources, music);
+    }
+
+    pub fn set_level(&mut self, level: usize) {
+        self.level = level;
+    }
+
+    /// Speed of the simulation, 1 for the normal speed.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.time_control = TimeControl::new(speed);
+    }
+
+    /// Plays the inputs of a replay in the next fights, the keyboard only pauses the game.
+    pub fn play_replay(&mut self, replay: ReplayPlayer) {
+        self.replay = Some(replay);
+    }
+
+    /// Records the inputs of the next fights, saved to `path` by `finish`.
+    pub fn record(&mut self, path: &str) {
+        self.recorder = Some((ReplayRecorder::new(), path.to_string()));
+    }
+
+    /// Plays the next fights online, the local player being the fighter of the session side.
+    pub fn play_online(&mut self, session: RollbackSession) {
+        self.online = Some(session);
+    }
+
+    /// Starts the game on the menu or directly in a fight.
+    pub fn start(&mut self, mode: StartMode, characters: &[String]) {
+        match mode {
+            StartMode::Menu => {}
+            StartMode::Versus => self.load_level(characters),
+            StartMode::Training => {
+                self.training = Some(Training::new());
+                self.load_level(characters);
+            }
+        }
+    }
+
+    /// Saves the recorded inputs, when recording.
+    pub fn finish(&mut self) {
+        if let Some((recorder, path)) = &self.recorder {
+            match recorder.replay.save(path) {
+                Ok(()) => log::info!("replay saved to {}", path),
+                Err(e) => log::error!("unable to save replay {} : {}", path, e),
+            }
+        }
+    }
+
+    /// Forwards the replay events of the tick about to run to the selected fighter.
+    fn play_replay_events(&mut self) {
+        let events = match &mut self.replay {
+            Some(replay) => replay.due_events(),
+            None => return,
+        };
+        for event in events {
+            for fighter in self.fighters.iter_mut().filter(|x| x.selected) {
+                fighter.process_event(event);
+            }
+        }
+    }
+
+    fn play_sounds(&mut self) {
+        for fighter in self.fighters.iter_mut() {
+            for sound in fighter.take_sounds() {
+                self.audio.play(&self.resources, &sound);
+            }
+        }
+        if let Some(sound) = self.menu.take_sound() {
+            self.audio.play(&self.resources, sound);
+        }
+    }
+
+    /// Reloads the configuration when it changes on disk and applies it to the resources,
+    /// the menus and the fighters.
+    fn check_reload(&mut self) {
+        let changed = self.watcher.poll();
+        if changed.is_empty() {
+            return;
+        }
+        // the configuration files and the Aseprite exports it imports are data files
+        let is_data = |path: &PathBuf| Format::is_data_file(path);
+        let mut assets_changed = changed.iter().any(|path| !is_data(path));
+        if changed.iter().any(is_data) {
+            match Configuration::load(&self.resources.assets, &self.configuration_path) {
+                Ok(configuration) => {
+                    log::info!("configuration reloaded");
+                    for fighter in self.fighters.iter_mut() {
+                        if let Some(character) = configuration.get_character(fighter.get_name()) {
+                            fighter.reload(character);
+                        }
+                    }
+                    self.watcher.watch(watched_files(&self.resources.assets, &self.configuration_path, &configuration));
+                    // the sheet areas or the files of the textures may have changed
+                    self.resources.declare_textures(&configuration);
+                    assets_changed = true;
+                    self.level = self.level.min(configuration.levels.len().saturating_sub(1));
+                    self.resources.configuration = configuration;
+                    self.menu.reload(&self.resources);
+                    self.font = BitmapFont::new(&self.resources);
+                    self.input_display = InputDisplay::new(&self.resources);
+                    self.reload_message = None;
+                }
+                Err(e) => {
+                    log::warn!("{}", e);
+                    self.reload_message = Some(e.to_string());
+                }
+            }
+        }
+        if assets_changed {
+            self.reload_assets();
+        }
+    }
+
+    /// Loads the textures and the sounds again, the sprites using them show the new ones.
+    fn reload_assets(&mut self) {
+        let sounds = self.resources.reload_sounds();
+        log::info!("{} sound(s) reloaded", sounds);
+        let errors = self.resources.reload_textures();
+        match errors.first() {
+            None => log::info!("textures reloaded"),
+            Some(e) if errors.len() == 1 => self.reload_message = Some(e.to_string()),
+            Some(e) => self.reload_message = Some(format!("{} (+{})", e, errors.len() - 1)),
+        }
+    }
+
+    fn draw_reload_message(&mut self) {
+        if let Some(message) = &self.reload_message {
+            self.screen.window.set_view(&self.screen.gui_view);
+            self.font.set_scale(0.3);
+            self.font.set_color(Color::RED);
+            self.font.draw(&mut self.screen.window, message, Vector2f::new(4., 4.), Align::Left);
         }
     }
 
     fn draw_update_frame_arena(&mut self) {
-        self.arena.draw(&mut self.window);
+        if let Some(arena) = &mut self.arena {
+            arena.draw(&mut self.screen.window);
+        }
+    }
+
+    /// Animated loading screen with the progress of the decoding.
+    fn draw_loading(&mut self) {
+        let progress = self.loading.as_ref().map_or(1., |(loader, _)| loader.progress());
+        let window = &mut self.screen.window;
+        window.set_view(&self.screen.gui_view);
+        let center = Vector2f::new(GAME_WIDTH / 2., GAME_HEIGHT / 2.);
+        self.font.set_scale(0.5);
+        self.font.set_color(Color::WHITE);
+        self.font.draw(window, "chargement", center - Vector2f::new(0., 20.), Align::Center);
+        let left = center.x - LOADING_BAR_WIDTH / 2.;
+        let mut bar = RectangleShape::with_size(Vector2f::new(LOADING_BAR_WIDTH, LOADING_BAR_HEIGHT));
+        bar.set_position(Vector2f::new(left, center.y));
+        bar.set_fill_color(Color::TRANSPARENT);
+        bar.set_outline_color(Color::WHITE);
+        bar.set_outline_thickness(1.);
+        window.draw(&bar);
+        bar.set_size(Vector2f::new(LOADING_BAR_WIDTH * progress, LOADING_BAR_HEIGHT));
+        bar.set_fill_color(Color::YELLOW);
+        bar.set_outline_thickness(0.);
+        window.draw(&bar);
+        self.loading_cursor.set_position(Vector2f::new(left - 15., center.y + LOADING_BAR_HEIGHT / 2.));
+        self.loading_cursor.draw(window);
+    }
+
+    /// Runs a tick of the fight : replay inputs, simulation, training and recording.
+    fn update_tick(&mut self) {
+        self.play_replay_events();
+        self.simulate_tick();
+        if let Some(training) = &mut self.training {
+            training.update(&mut self.fighters, self.menu.settings.dummy, &self.resources.configuration);
+        }
+        if let Some((recorder, _)) = &mut self.recorder {
+            recorder.tick();
+        }
+    }
+
+    /// Simulates a tick from the inputs given to the fighters : fighters, camera and
+    /// attacks, the training counting them itself. A rollback runs it alone.
+    fn simulate_tick(&mut self) {
+        for fighter in self.fighters.iter_mut() {
+            fighter.on_frame_update();
+            if fighter.selected {
+                self.screen.view.move_(fighter.get_speed())
+            }
+        }
+        if self.training.is_none() {
+            self.resolve_attacks();
+        }
+    }
+
+    /// Applies the attacks of the first two fighters touching each other.
+    fn resolve_attacks(&mut self) {
+        if let [first, second, ..] = self.fighters.as_mut_slice() {
+            first.attack(second);
+            second.attack(first);
+        }
+    }
+
+    /// Gives the inputs of a tick of the online session to the fighters.
+    fn apply_inputs(&mut self, session: &mut RollbackSession, tick: u32) {
+        let (current, previous) = session.inputs(tick);
+        for (i, fighter) in self.fighters.iter_mut().take(2).enumerate() {
+            for event in current[i].events(previous[i]) {
+                fighter.process_event(event);
+            }
+        }
+    }
+
+    /// Exchanges the inputs with the peer, goes back to the last right state when a
+    /// remote input was mispredicted, then simulates the ticks due if the peer is not too late.
+    fn update_online(&mut self, frame_time: f32) {
+        if !matches!(self.display, DisplayState::Game | DisplayState::Pause) {
+            return;
+        }
+        let mut session = match self.online.take() {
+            Some(session) => session,
+            None => return,
+        };
+        if let Some(tick) = session.receive() {
+            self.rollback(&mut session, tick);
+        }
+        if session.is_timed_out() {
+            log::warn!("no news of the other player, online versus stopped");
+            self.back_to_title();
+            return;
+        }
+        for _ in 0..self.time_control.ticks(frame_time) {
+            if !session.can_advance() {
+                break;
+            }
+            let tick = session.tick();
+            session.read_local_input();
+            session.save(tick, self.snapshot());
+            self.apply_inputs(&mut session, tick);
+            self.update_tick();
+            session.advance();
+            self.time_control.set_tick(session.tick() as u64);
+        }
+        session.send();
+        self.online = Some(session);
+    }
+
+    /// Restores the fight at a tick and simulates again the ticks since, with the inputs now known.
+    fn rollback(&mut self, session: &mut RollbackSession, tick: u32) {
+        let snapshot = match session.snapshot(tick) {
+            Some(snapshot) => snapshot.clone(),
+            None => {
+                log::error!("no snapshot to roll back to tick {}", tick);
+                return;
+            }
+        };
+        if let Err(e) = self.restore(&snapshot) {
+            log::error!("{}", e);
+            return;
+        }
+        log::debug!("rollback of {} tick(s) to tick {}", session.tick() - tick, tick);
+        for replayed in tick..session.tick() {
+            self.time_control.set_tick(replayed as u64);
+            session.save(replayed, self.snapshot());
+            self.apply_inputs(session, replayed);
+            self.simulate_tick();
+        }
+        self.time_control.set_tick(session.tick() as u64);
+        // the sounds of these ticks were played when they were predicted
+        for fighter in self.fighters.iter_mut() {
+            fighter.take_sounds();
+        }
     }
 
-    fn draw_update_frame(&mut self, resources: &GameResources) {
+    fn draw_online_status(&mut self) {
+        let waiting = self.online.as_ref().is_some_and(|x| !x.is_connected());
+        if waiting && matches!(self.display, DisplayState::Game) {
+            self.screen.window.set_view(&self.screen.gui_view);
+            self.font.set_scale(0.4);
+            self.font.set_color(Color::WHITE);
+            let position = Vector2f::new(GAME_WIDTH / 2., GAME_HEIGHT / 3.);
+            self.font.draw(&mut self.screen.window, "en attente de l'adversaire", position, Align::Center);
+        }
+    }
+
+    fn draw_fight(&mut self) {
+        self.draw_update_frame_arena();
+        for fighter in self.fighters.iter() {
+            fighter.draw(&mut self.screen.window);
+        }
+    }
+
+    fn draw_update_frame(&mut self, frame_time: f32) {
         match self.display {
             DisplayState::Game => {
-                self.draw_update_frame_arena();
-                for fighter in self.fighters.iter_mut() {
-                    //println!("FIGHTER : {}", fighter.get_name());
-                    fighter.on_frame_update(&mut self.window);
-                    if fighter.selected {
-                        self.view.move_(fighter.get_speed())
+                // online, the session runs the ticks
+                if self.online.is_none() {
+                    for _ in 0..self.time_control.ticks(frame_time) {
+                        self.update_tick();
                     }
                 }
+                self.draw_fight();
             }
             DisplayState::Menu => {
-                self.menu.draw(&mut self.window, resources);
+                self.screen.window.set_view(&self.screen.gui_view);
+                self.menu.update(frame_time);
+                self.menu.draw(&mut self.screen.window);
+            }
+            DisplayState::Loading => self.draw_loading(),
+            DisplayState::Pause => {
+                self.draw_fight();
+                self.screen.window.set_view(&self.screen.gui_view);
+                self.menu.update(frame_time);
+                self.menu.draw(&mut self.screen.window);
             }
         }
     }
 
-    pub fn render_frame(&mut self, resources: &GameResources) {
-        self.window.clear(Color::BLACK);
-        self.draw_update_frame(resources);
-        self.window.set_view(&self.view);
-        self.window.display();
+    fn draw_training(&mut self) {
+        if let (Some(training), DisplayState::Game | DisplayState::Pause) = (&self.training, self.display) {
+            self.screen.window.set_view(&self.screen.gui_view);
+            training.draw(&mut self.screen.window, &mut self.font, self.menu.settings.dummy);
+        }
     }
 
-    fn load_solo_level(&mut self, resources: &'a GameResources) {
+    fn draw_input_display(&mut self) {
+        if self.menu.settings.input_display && matches!(self.display, DisplayState::Game | DisplayState::Pause) {
+            self.screen.window.set_view(&self.screen.gui_view);
+            self.input_display.draw(&mut self.screen.window, &mut self.font, &self.fighters);
+        }
+    }
+
+    fn draw_debug_overlay(&mut self) {
+        if !self.debug.visible {
+            return;
+        }
+        if matches!(self.display, DisplayState::Game | DisplayState::Pause) {
+            self.screen.window.set_view(&self.screen.view);
+            self.debug.draw_fighters(&mut self.screen.window, &self.fighters);
+        }
+        self.screen.window.set_view(&self.screen.gui_view);
+        self.debug.draw_texts(
+            &mut self.screen.window,
+            &mut self.font,
+            &self.fighters,
+            &self.time_control.describe(),
+        );
+    }
+
+    pub fn render_frame(&mut self) {
+        let tick = Clock::start();
+        let frame_time = self.frame_clock.restart().as_microseconds() as f32 / 1000.;
+        self.check_reload();
+        self.update_loading();
+        self.update_online(frame_time);
+        self.screen.window.clear(Color::BLACK);
+        self.screen.window.set_view(&self.screen.view);
+        self.draw_update_frame(frame_time);
+        self.draw_training();
+        self.draw_online_status();
+        self.draw_input_display();
+        self.draw_debug_overlay();
+        self.draw_reload_message();
+        self.play_sounds();
+        self.debug.end_frame(tick.elapsed_time());
+        self.screen.window.display();
+    }
+
+    /// Prepares a fight, the textures it uses are decoded in the background
+    /// while the loading screen is shown.
+    fn load_level(&mut self, characters: &[String]) {
+        let level = &self.resources.configuration.levels[self.level];
+        let mut ids = vec![resources::level_texture(&level.name)];
+        ids.extend(characters.iter().map(|x| resources::character_texture(x)));
+        let files = self.resources.pending_textures(&ids);
+        log::debug!("{} texture(s) to load", files.len());
+        let loader = ImageLoader::start(self.resources.assets.clone(), files);
+        self.loading = Some((loader, characters.to_vec()));
+        self.display = DisplayState::Loading;
+    }
+
+    /// Creates the textures of the images decoded, then starts the fight when all are ready.
+    fn update_loading(&mut self) {
+        let loader = match &mut self.loading {
+            Some((loader, _)) => loader,
+            None => return,
+        };
+        for (id, image) in loader.poll() {
+            if let Err(e) = image.and_then(|x| self.resources.upload_texture(&id, x.image())) {
+                log::error!("{}", e);
+            }
+        }
+        if loader.is_finished() {
+            if let Some((_, characters)) = self.loading.take() {
+                self.start_fight(&characters);
+            }
+        }
+    }
+
+    /// Starts a fight, the first character being the player one.
+    fn start_fight(&mut self, characters: &[String]) {
+        self.fighters.clear();
+        for (i, character) in characters.iter().enumerate() {
+            let position = start_position(i);
+            match Fighter::new(character, &self.resources, character, position.x, position.y, i == 0) {
+                Ok(fighter) => self.fighters.push(fighter),
+                Err(e) => {
+                    log::error!("{}", e);
+                    self.reload_message = Some(e.to_string());
+                    self.fighters.clear();
+                    self.display = DisplayState::Menu;
+                    self.menu.open("title");
+                    return;
+                }
+            }
+        }
+        if self.arena.as_ref().map(|x| x.level) != Some(self.level) {
+            if let Some(arena) = &self.arena {
+                self.resources.unload_texture(&arena.texture(&self.resources));
+            }
+            self.arena = Some(Arena::new(&self.resources, self.level));
+        }
+        if self.training.is_some() {
+            self.reset_positions();
+        }
+        self.display = DisplayState::Game;
+        self.time_control.restart();
+        let level = &self.resources.configuration.levels[self.level];
+        if let Some((recorder, _)) = &mut self.recorder {
+            recorder.restart(characters, &level.name);
+        }
+        if let Some(replay) = &mut self.replay {
+            replay.restart();
+        }
+        if let Some(session) = &mut self.online {
+            session.restart(netplay::fight_id(characters, &level.name));
+        }
+        self.audio.play_music(&self.resources, level.music.as_deref());
+    }
+
+    /// Puts the fighters back at their start position, facing each other.
+    fn reset_positions(&mut self) {
+        for (i, fighter) in self.fighters.iter_mut().enumerate() {
+            let direction = if i == 0 { Direction::Right } else { Direction::Left };
+            fighter.reset_position(start_position(i), direction);
+        }
+        self.screen.reset_view();
+        if let Some(training) = &mut self.training {
+            training.reset();
+        }
+    }
+
+    /// State of the fight at the current tick.
+    fn snapshot(&self) -> FightSnapshot {
+        FightSnapshot {
+            level: self.resources.configuration.levels[self.level].name.clone(),
+            tick: self.time_control.tick(),
+            camera: self.screen.view.center(),
+            fighters: self.fighters.iter().map(|x| x.snapshot()).collect(),
+            training: self.training.as_ref().map(|x| x.snapshot()),
+        }
+    }
+
+    /// Goes back to a saved state of the fight, which must have the same level and characters.
+    fn restore(&mut self, snapshot: &FightSnapshot) -> Result<(), String> {
+        let level = &self.resources.configuration.levels[self.level].name;
+        if &snapshot.level != level {
+            return Err(format!("save state of level {}, not {}", snapshot.level, level));
+        }
+        let names: Vec<&str> = self.fighters.iter().map(|x| x.get_name()).collect();
+        if !snapshot.fighters.iter().map(|x| x.name.as_str()).eq(names.iter().copied()) {
+            return Err(format!("save state of other characters than {}", names.join(", ")));
+        }
+        for (fighter, state) in self.fighters.iter_mut().zip(&snapshot.fighters) {
+            fighter.restore(state);
+        }
+        if let (Some(training), Some(state)) = (&mut self.training, &snapshot.training) {
+            training.restore(state);
+        }
+        self.time_control.set_tick(snapshot.tick);
+        if let Some(replay) = &mut self.replay {
+            replay.seek(snapshot.tick as u32);
+        }
+        self.screen.view.set_center(snapshot.camera);
+        Ok(())
+    }
+
+    /// Save state hotkeys : F10 saves the fight and F12 restores it, in memory,
+    /// or in the save state file with shift.
+    fn process_save_state_event(&mut self, e: Event) -> bool {
+        match e {
+            Event::KeyPressed { code: Key::F10, shift, .. } => {
+                let snapshot = self.snapshot();
+                if shift {
+                    match snapshot.save(SAVE_STATE_FILE) {
+                        Ok(()) => log::info!("save state written to {}", SAVE_STATE_FILE),
+                        Err(e) => log::error!("unable to write save state {} : {}", SAVE_STATE_FILE, e),
+                    }
+                } else {
+                    log::info!("state saved at tick {}", snapshot.tick);
+                }
+                self.save_state = Some(snapshot);
+            }
+            Event::KeyPressed { code: Key::F12, shift, .. } => {
+                let snapshot = if shift {
+                    FightSnapshot::load(SAVE_STATE_FILE).map_err(|e| e.to_string())
+                } else {
+                    self.save_state.clone().ok_or_else(|| String::from("no state saved"))
+                };
+                match snapshot.and_then(|x| self.restore(&x)) {
+                    Ok(()) => log::info!("state restored at tick {}", self.time_control.tick()),
+                    Err(e) => log::warn!("{}", e),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Hotkeys of the debug mode controlling the simulation : P freezes and resumes it,
+    /// N runs one tick, minus and plus slow it down or speed it up.
+    fn process_time_event(&mut self, e: Event) -> bool {
+        if !self.debug.visible {
+            return false;
+        }
+        match e {
+            Event::KeyPressed { code: Key::P, .. } => self.time_control.toggle_freeze(),
+            Event::KeyPressed { code: Key::N, .. } => self.time_control.step(),
+            Event::KeyPressed { code: Key::Hyphen | Key::Subtract, .. } => self.time_control.change_speed(true),
+            Event::KeyPressed { code: Key::Equal | Key::Add, .. } => self.time_control.change_speed(false),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Hotkeys of the training mode and inputs of the dummy when the keyboard
+    /// controls it, returns true when the event is handled.
+    fn process_training_event(&mut self, e: Event) -> bool {
+        if self.training.is_none() {
+            return false;
+        }
+        match e {
+            Event::KeyPressed { code: Key::F4, .. } => self.reset_positions(),
+            Event::KeyPressed { code: Key::F5, .. } => {
+                self.menu.settings.next_dummy(true);
+                if let Err(e) = self.menu.settings.save() {
+                    log::error!("unable to save settings : {}", e);
+                }
+            }
+            _ => {
+                let (training, dummy) = match (&mut self.training, self.fighters.get_mut(1)) {
+                    (Some(training), Some(dummy)) => (training, dummy),
+                    _ => return false,
+                };
+                if training.process_event(e, dummy) {
+                    return true;
+                }
+                let is_pause = matches!(e, Event::KeyPressed { code: Key::Escape, .. });
+                if !training.controls_dummy() || is_pause {
+                    return false;
+                }
+                training.process_dummy_event(e, dummy);
+            }
+        }
+        true
+    }
+
+    fn mouse_position(&self, x: i32, y: i32) -> Vector2f {
+        self.screen
+            .window
+            .map_pixel_to_coords(Vector2i::new(x, y), &self.screen.gui_view)
+    }
+
+    fn back_to_title(&mut self) {
         self.fighters.clear();
-        self.fighters.push(Box::new(Fighter::new(
-            "Maurice", &resources, "Maurice", 120., 150., true,
-        )));
+        self.training = None;
+        self.save_state = None;
+        self.online = None;
+        self.screen.reset_view();
+        self.menu.open("title");
+        self.display = DisplayState::Menu;
+        self.play_menu_music();
+    }
+
+    /// Forwards an event to the menu, translating mouse positions to gui coordinates.
+    fn process_menu_event(&mut self, e: Event) -> MenuEvent {
+        match e {
+            Event::MouseMoved { x, y } => {
+                let position = self.mouse_position(x, y);
+                self.menu.on_mouse_move(position);
+                MenuEvent::Nothing
+            }
+            Event::MouseButtonPressed { button, x, y } => {
+                let position = self.mouse_position(x, y);
+                self.menu.on_click(position, button)
+            }
+            _ => self.menu.process_event(e),
+        }
+    }
+
+    /// Window level events, handled whatever the current display.
+    fn process_window_event(&mut self, e: Event) -> bool {
+        match e {
+            Event::Resized { width, height } => {
+                self.screen.on_resize(width, height);
+                true
+            }
+            Event::KeyPressed { code: Key::F2, .. } => {
+                self.menu.settings.change("input_display", true);
+                if let Err(e) = self.menu.settings.save() {
+                    log::error!("unable to save settings : {}", e);
+                }
+                true
+            }
+            Event::KeyPressed { code: Key::F3, .. } => {
+                self.debug.toggle();
+                true
+            }
+            Event::KeyPressed { code: Key::F11, .. }
+            | Event::KeyPressed {
+                code: Key::Enter,
+                alt: true,
+                ..
+            } => {
+                self.screen.toggle_fullscreen();
+                self.menu.settings.fullscreen = self.screen.configuration.fullscreen;
+                true
+            }
+            _ => false,
+        }
     }
 
-    pub fn process_input_event(&mut self, e: Event, resources: &'a GameResources) -> bool {
+    /// Applies the display settings that can change while the game runs.
+    fn apply_settings(&mut self) {
+        let settings = &self.menu.settings;
+        if settings.fullscreen != self.screen.configuration.fullscreen {
+            self.screen.toggle_fullscreen();
+        }
+        if settings.scaling != self.screen.configuration.scaling {
+            self.screen.set_scaling(settings.scaling);
+        }
+        self.audio.set_volumes(settings);
+    }
+
+    pub fn process_input_event(&mut self, e: Event) -> bool {
         let mut end_game = false;
+        if let Event::Closed = e {
+            return true;
+        }
+        if self.process_window_event(e) {
+            return false;
+        }
         match self.display {
+            DisplayState::Game if self.online.is_some() => {
+                // the time and save state hotkeys would desynchronize the players
+                let session = self.online.as_mut().unwrap();
+                if let Event::KeyPressed { code: Key::Escape, .. } = e {
+                    session.local_input.clear();
+                    self.display = DisplayState::Pause;
+                    self.menu.open("pause");
+                } else {
+                    session.local_input.on_event(e);
+                }
+            }
             DisplayState::Game => {
+                if self.process_time_event(e)
+                    || self.process_save_state_event(e)
+                    || self.process_training_event(e)
+                {
+                    return false;
+                }
+                let is_replay = self.replay.is_some();
+                let is_pause = matches!(e, Event::KeyPressed { code: Key::Escape, .. });
+                if let (Some((recorder, _)), false) = (&mut self.recorder, is_pause) {
+                    recorder.record(&e);
+                }
                 for fighter in &mut self.fighters {
-                    if fighter.selected {
-                        if fighter.as_mut().process_event(e) == ResultEvent::Menu {
-                            self.display = DisplayState::Menu;
-                        }
+                    if fighter.selected
+                        && (!is_replay || is_pause)
+                        && fighter.process_event(e) == ResultEvent::Menu
+                    {
+                        self.display = DisplayState::Pause;
+                        self.menu.open("pause");
                     }
                 }
             }
-            DisplayState::Menu => match self.menu.process_event(e) {
-                ResultEvent::Exit => {
+            DisplayState::Menu => match self.process_menu_event(e) {
+                MenuEvent::Closed | MenuEvent::Action(MenuAction::Quit, _) => {
                     end_game = true;
                 }
-                ResultEvent::Solo => {
+                // the validation requires the character of these actions
+                MenuEvent::Action(MenuAction::Solo, Some(character)) => {
+                    self.training = None;
+                    self.load_level(&[character]);
+                }
+                MenuEvent::Action(MenuAction::Training, Some(character)) => {
+                    self.training = Some(Training::new());
+                    self.load_level(&[character.clone(), character]);
+                }
+                MenuEvent::SettingsChanged => self.apply_settings(),
+                _ => {}
+            },
+            // the fight starts when its textures are ready
+            DisplayState::Loading => {}
+            DisplayState::Pause => match self.process_menu_event(e) {
+                MenuEvent::Closed | MenuEvent::Action(MenuAction::Resume, _) => {
                     self.display = DisplayState::Game;
-                    self.load_solo_level(resources);
                 }
+                MenuEvent::Action(MenuAction::Title, _) => self.back_to_title(),
+                MenuEvent::Action(MenuAction::Quit, _) => {
+                    end_game = true;
+                }
+                MenuEvent::SettingsChanged => self.apply_settings(),
                 _ => {}
             },
         }
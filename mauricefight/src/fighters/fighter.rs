@@ -1,16 +1,24 @@
+use crate::audio::{SOUND_BLOCK, SOUND_HIT, SOUND_KO};
 use crate::common::Direction;
 use crate::common::InputProcessor;
 use crate::common::ResultEvent;
-use crate::configuration::resources::GameResources;
+use crate::configuration::configuration::{AnimationEvent, CharacterConfiguration, HitBoxConfiguration};
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::resources::{self, GameResources};
 use crate::fighters::fighter_crouch::FighterCrouch;
 use crate::fighters::fighter_crouch::FighterEndCrouch;
 use crate::fighters::fighter_input::FighterInputState;
+use crate::fighters::fighter_ko::FighterKo;
 use crate::fighters::fighter_move::FighterMove;
+use crate::fighters::fighter_state::FighterAnimation;
 use crate::fighters::fighter_state::FighterState;
 use crate::fighters::fighter_state::State;
+use crate::fighters::fighter_state::unknown_character;
 use crate::fighters::fighter_waiting::FighterWaiting;
-use crate::sprites::animated_sprite::AnimatedSprite;
-use sfml::graphics::RenderWindow;
+use crate::fighters::input_history::InputHistory;
+use crate::sprites::animated_sprite::{AnimatedSprite, AnimationInfo, NavInfo};
+use serde_derive::{Deserialize, Serialize};
+use sfml::graphics::{FloatRect, RenderWindow};
 use sfml::system::Vector2f;
 use sfml::window::Event;
 use sfml::window::Key;
@@ -19,59 +27,126 @@ use super::fighter_high_kick::FighterHighKick;
 use crate::fighters::fighter_punch::FighterLeftPunch;
 use crate::fighters::fighter_punch::FighterRightPunch;
 
-pub struct Fighter<'a> {
+/// Kinds of collision boxes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoxKind {
+    /// areas hit by the attacks : body, head and legs
+    Hurt,
+    /// attack area
+    Hit,
+    /// area the other fighters can't enter
+    Push,
+}
+
+/// Vertical speed at the start of a jump, in pixels per frame.
+const JUMP_SPEED: f32 = 4.;
+/// Speed gained by a falling fighter at each frame.
+const GRAVITY: f32 = 0.2;
+/// Health at the start of a fight, the fighter is knocked out at 0.
+pub const MAX_HEALTH: i32 = 100;
+
+fn max_health() -> i32 {
+    MAX_HEALTH
+}
+
+/// State of a fighter at a tick, restored by `Fighter::restore`. The
+/// animations of the states come from the configuration and aren't saved.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FighterSnapshot {
+    pub name: String,
+    previous_state: State,
+    current_state: State,
+    direction: Direction,
+    is_new_state: bool,
+    /// first sheet frame of the animation
+    sprite_index: i32,
+    animation: AnimationInfo,
+    nav: NavInfo,
+    input_state: FighterInputState,
+    /// saved so that the inputs simulated again by a rollback aren't shown twice
+    #[serde(default)]
+    input_history: InputHistory,
+    hitbox_active: bool,
+    cancellable: bool,
+    ground: Option<f32>,
+    #[serde(default = "max_health")]
+    health: i32,
+    #[serde(default)]
+    touched: bool,
+}
+
+pub struct Fighter {
     name: String,
     previous_state: State,
     current_state: State,
     current_direction: Direction,
     states: HashMap<State, Box<dyn FighterState>>,
-    sprite: AnimatedSprite<'a>,
+    sprite: AnimatedSprite,
     is_new_state: bool,
     pub selected: bool,
     input_state: FighterInputState,
+    input_history: InputHistory,
+    sounds: Vec<String>,
+    /// the attack hitbox of the current action is active
+    hitbox_active: bool,
+    /// the current action can be cancelled by a new one
+    cancellable: bool,
+    /// height of the ground while jumping
+    ground: Option<f32>,
+    health: i32,
+    /// the attack of the current action already touched, it hits once
+    touched: bool,
 }
 
-impl<'a> Fighter<'a> {
+impl Fighter {
     pub fn new(
         name: &str,
-        resources: &'a GameResources,
+        resources: &GameResources,
         sprite: &str,
         x: f32,
         y: f32,
         selected: bool,
-    ) -> Self {
+    ) -> Result<Self, ConfigurationError> {
         let mut states: HashMap<State, Box<dyn FighterState>> = HashMap::new();
         states.insert(
             State::Idle,
-            Box::new(FighterWaiting::new(name, resources, Direction::Right)),
+            Box::new(FighterWaiting::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::Crouch,
-            Box::new(FighterCrouch::new(name, resources, Direction::Right)),
+            Box::new(FighterCrouch::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::Move,
-            Box::new(FighterMove::new(name, resources, Direction::Right)),
+            Box::new(FighterMove::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::EndCrouch,
-            Box::new(FighterEndCrouch::new(name, resources, Direction::Right)),
+            Box::new(FighterEndCrouch::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::HighKick,
-            Box::new(FighterHighKick::new(name, resources, Direction::Right)),
+            Box::new(FighterHighKick::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::LeftPunch,
-            Box::new(FighterLeftPunch::new(name, resources, Direction::Right)),
+            Box::new(FighterLeftPunch::new(name, resources, Direction::Right)?),
         );
         states.insert(
             State::RightPunch,
-            Box::new(FighterRightPunch::new(name, resources, Direction::Right)),
+            Box::new(FighterRightPunch::new(name, resources, Direction::Right)?),
         );
-        let configuration = resources.configuration.get_character(name).unwrap();
-        let default_action_config = configuration.get_action_configuration("idle").unwrap();
-        Fighter {
+        states.insert(
+            State::Ko,
+            Box::new(FighterKo::new(name, resources, Direction::Right)?),
+        );
+        let configuration = resources
+            .configuration
+            .get_character(name)
+            .ok_or_else(|| unknown_character(name))?;
+        let idle = states[&State::Idle].get_animation_state();
+        let (sprite_index, delay, nb_frames) = (idle.sprite_index, idle.delay, idle.nb_frames);
+        Ok(Fighter {
             name: name.to_string(),
             previous_state: State::Idle,
             current_state: State::Idle,
@@ -79,57 +154,355 @@ impl<'a> Fighter<'a> {
             states,
             sprite: AnimatedSprite::new(
                 resources,
-                sprite,
-                configuration.sprite.size,
+                &resources::character_texture(sprite),
+                &configuration.sprite,
                 configuration.sprite.display.scale,
                 x,
                 y,
                 0.,
                 0.,
                 Direction::Right,
-                default_action_config.sequence.index,
-                default_action_config.sequence.delay,
-                default_action_config.sequence.nb_frames,
+                sprite_index,
+                delay,
+                nb_frames,
             ),
             is_new_state: true,
             selected,
             input_state: FighterInputState::new(),
-        }
+            input_history: InputHistory::new(),
+            sounds: Vec::new(),
+            hitbox_active: false,
+            cancellable: false,
+            ground: None,
+            health: MAX_HEALTH,
+            touched: false,
+        })
     }
 
-    pub fn on_frame_update(&mut self, window: &mut RenderWindow) {
-        let mut _state = self.states.get_mut(&self.current_state);
-        //println!("STATE {}", self.current_state);
-        match _state {
-            Some(s) => {
-                if self.is_new_state {
-                    let animation_state = s.get_animation_state();
-                    self.sprite.reset_animation(
-                        animation_state.sprite_index,
-                        animation_state.delay, 
-                        animation_state.nb_frames, 
-                        self.current_direction, 
-                        animation_state.speed, 
-                    );
-                    self.is_new_state = false;
+    /// Runs a simulation tick : animation, movement and state changes.
+    pub fn on_frame_update(&mut self) {
+        self.input_history.tick();
+        if let Some(s) = self.states.get_mut(&self.current_state) {
+            if self.is_new_state {
+                let animation_state = s.get_animation_state();
+                self.sprite.reset_animation(
+                    animation_state.sprite_index,
+                    animation_state.delay, 
+                    animation_state.nb_frames, 
+                    animation_state.steps.clone(),
+                    &animation_state.durations,
+                    self.current_direction, 
+                    animation_state.speed, 
+                );
+                if self.ground.is_none() {
+                    self.sprite.nav.speed.y = 0.;
                 }
-                let next_state = s.on_frame_update(&mut self.sprite, &self.input_state, window);
-                if next_state.0 != self.current_state || next_state.1 != self.current_direction {
-                    println!(
-                        "change : state : {}, direction : {}",
-                        self.current_state, self.current_direction
-                    );
-                    self.current_state = next_state.0;
-                    self.current_direction = next_state.1;
-                    self.is_new_state = true;
+                if let Some(sound) = &animation_state.sound {
+                    self.sounds.push(sound.clone());
+                }
+                self.hitbox_active = false;
+                self.cancellable = false;
+                self.touched = false;
+                self.is_new_state = false;
+            }
+            let next_state = s.on_frame_update(&mut self.sprite, &self.input_state);
+            if let Some(step) = self.sprite.animation.take_entered_step() {
+                let events = s.get_animation_state().events.get(step as usize).cloned().unwrap_or_default();
+                for event in events {
+                    self.apply_event(&event);
                 }
             }
-            _ => {}
+            if next_state.0 != self.current_state || next_state.1 != self.current_direction {
+                self.change_state(next_state.0, next_state.1);
+            }
+        }
+        self.update_jump();
+    }
+
+    /// Makes the fighter fall back to the ground it jumped from.
+    fn update_jump(&mut self) {
+        if let Some(ground) = self.ground {
+            let nav = &mut self.sprite.nav;
+            if nav.position.y >= ground && nav.speed.y >= 0. {
+                nav.position.y = ground;
+                nav.speed.y = 0.;
+                self.ground = None;
+            } else {
+                nav.speed.y += GRAVITY;
+            }
+        }
+    }
+
+    /// Jumps straight up, unless already in the air.
+    pub fn jump(&mut self) {
+        if self.ground.is_none() {
+            self.ground = Some(self.sprite.nav.position.y);
+            self.sprite.nav.speed.y = -JUMP_SPEED;
+        }
+    }
+
+    /// Puts the fighter back at `position`, idle, healed and facing `direction`.
+    pub fn reset_position(&mut self, position: Vector2f, direction: Direction) {
+        self.sprite.nav.position = position;
+        self.sprite.nav.speed = Vector2f::new(0., 0.);
+        self.ground = None;
+        self.health = MAX_HEALTH;
+        self.input_state.direction = direction;
+        self.change_state(State::Idle, direction);
+    }
+
+    fn change_state(&mut self, state: State, direction: Direction) {
+        log::debug!(
+            "{} : {} {} -> {} {}",
+            self.name, self.current_state, self.current_direction, state, direction
+        );
+        if state == State::Ko && self.current_state != State::Ko {
+            self.sounds.push(SOUND_KO.to_string());
+        }
+        self.current_state = state;
+        self.current_direction = direction;
+        self.is_new_state = true;
+    }
+
+    /// Applies the effects of an animation event shared by all the states.
+    fn apply_event(&mut self, event: &AnimationEvent) {
+        match event {
+            AnimationEvent::HitboxStart => self.hitbox_active = true,
+            AnimationEvent::HitboxEnd => self.hitbox_active = false,
+            AnimationEvent::Sound { name } => self.sounds.push(name.clone()),
+            AnimationEvent::Impulse { x, y } => {
+                let x = if self.current_direction == Direction::Right { *x } else { -x };
+                self.sprite.nav.speed += Vector2f::new(x, *y);
+            }
+            AnimationEvent::Cancel => self.cancellable = true,
+        }
+    }
+
+    pub fn is_hitbox_active(&self) -> bool {
+        self.hitbox_active
+    }
+
+    /// The active hitbox touches a hurt box of `target`, for the first time in the action.
+    pub fn touches(&self, target: &Fighter) -> bool {
+        if self.touched || !self.hitbox_active {
+            return false;
+        }
+        let hurt_boxes = target.collision_boxes();
+        self.collision_boxes()
+            .iter()
+            .filter(|(kind, _)| *kind == BoxKind::Hit)
+            .any(|(_, hit)| {
+                hurt_boxes
+                    .iter()
+                    .any(|(kind, hurt)| *kind == BoxKind::Hurt && hit.intersection(hurt).is_some())
+            })
+    }
+
+    /// Hits `target` when the attack touches it : it takes the damage of the
+    /// action, unless it holds back to block.
+    pub fn attack(&mut self, target: &mut Fighter) {
+        if target.is_ko() || !self.touches(target) {
+            return;
         }
+        let damage = self
+            .states
+            .get(&self.current_state)
+            .map_or(0, |s| s.get_animation_state().damage);
+        let blocked = target.is_blocking(self.get_position().x);
+        self.register_touch();
+        target.take_hit(damage, blocked);
+    }
+
+    /// Marks the attack of the current action as touching, it won't touch again.
+    pub fn register_touch(&mut self) {
+        self.touched = true;
+    }
+
+    /// Holds the direction away from an attacker at `x`, without attacking.
+    fn is_blocking(&self, x: f32) -> bool {
+        let back = if x > self.get_position().x {
+            self.input_state.left_move && !self.input_state.right_move
+        } else {
+            self.input_state.right_move && !self.input_state.left_move
+        };
+        back && matches!(self.current_state, State::Idle | State::Move | State::Crouch)
+    }
+
+    /// Receives an attack : plays the block or hit sound and removes `damage` from
+    /// the health when not blocked, the fighter being knocked out at 0.
+    pub fn take_hit(&mut self, damage: i32, blocked: bool) {
+        if blocked {
+            self.sounds.push(SOUND_BLOCK.to_string());
+            return;
+        }
+        self.sounds.push(SOUND_HIT.to_string());
+        self.health = (self.health - damage).max(0);
+        if self.health == 0 {
+            self.change_state(State::Ko, self.current_direction);
+        }
+    }
+
+    pub fn is_ko(&self) -> bool {
+        self.current_state == State::Ko
+    }
+
+    /// Box of the current frame in world coordinates, mirrored when facing left.
+    fn world_box(&self, hitbox: &HitBoxConfiguration) -> FloatRect {
+        let scale = self.sprite.display.scale;
+        let position = self.sprite.nav.position;
+        let left = match self.current_direction {
+            Direction::Right => hitbox.left,
+            Direction::Left => -(hitbox.left + hitbox.width),
+        };
+        FloatRect::new(
+            position.x + left * scale.x,
+            position.y + hitbox.top * scale.y,
+            hitbox.width * scale.x,
+            hitbox.height * scale.y,
+        )
+    }
+
+    /// Collision boxes of the current step, the attack one while it is active.
+    pub fn collision_boxes(&self) -> Vec<(BoxKind, FloatRect)> {
+        let step = self.sprite.animation.step as usize;
+        let boxes = match self
+            .states
+            .get(&self.current_state)
+            .and_then(|s| s.get_animation_state().boxes.get(step))
+        {
+            Some(boxes) => boxes,
+            None => return Vec::new(),
+        };
+        let hit = boxes.hit.iter().filter(|_| self.is_hitbox_active());
+        boxes
+            .hurt
+            .iter()
+            .map(|x| (BoxKind::Hurt, x))
+            .chain(hit.map(|x| (BoxKind::Hit, x)))
+            .chain(boxes.push.iter().map(|x| (BoxKind::Push, x)))
+            .filter(|(_, x)| x.width > 0. && x.height > 0.)
+            .map(|(kind, x)| (kind, self.world_box(x)))
+            .collect()
+    }
+
+    /// Releases the directions held, when the fighter stops being controlled.
+    pub fn release_inputs(&mut self) {
+        let held = [
+            (self.input_state.left_move, Key::Left),
+            (self.input_state.right_move, Key::Right),
+            (self.input_state.crouch, Key::Down),
+        ];
+        for (_, code) in held.into_iter().filter(|(on, _)| *on) {
+            let (alt, ctrl, shift, system) = (false, false, false, false);
+            self.process_event(Event::KeyReleased { code, alt, ctrl, shift, system });
+        }
+    }
+
+    pub fn snapshot(&self) -> FighterSnapshot {
+        FighterSnapshot {
+            name: self.name.clone(),
+            previous_state: self.previous_state,
+            current_state: self.current_state,
+            direction: self.current_direction,
+            is_new_state: self.is_new_state,
+            sprite_index: self.sprite.sprite.index,
+            animation: self.sprite.animation.clone(),
+            nav: self.sprite.nav.clone(),
+            input_state: self.input_state.clone(),
+            input_history: self.input_history.clone(),
+            hitbox_active: self.hitbox_active,
+            cancellable: self.cancellable,
+            ground: self.ground,
+            health: self.health,
+            touched: self.touched,
+        }
+    }
+
+    /// Puts the fighter back in the state of a snapshot taken from a fighter of the same character.
+    pub fn restore(&mut self, snapshot: &FighterSnapshot) {
+        self.previous_state = snapshot.previous_state;
+        self.current_state = snapshot.current_state;
+        self.current_direction = snapshot.direction;
+        self.is_new_state = snapshot.is_new_state;
+        self.sprite
+            .restore(snapshot.sprite_index, &snapshot.animation, &snapshot.nav, snapshot.direction);
+        self.input_state = snapshot.input_state.clone();
+        self.input_history = snapshot.input_history.clone();
+        self.hitbox_active = snapshot.hitbox_active;
+        self.cancellable = snapshot.cancellable;
+        self.ground = snapshot.ground;
+        self.health = snapshot.health;
+        self.touched = snapshot.touched;
+        self.sounds.clear();
+    }
+
+    pub fn input_history(&self) -> &InputHistory {
+        &self.input_history
+    }
+
+    pub fn current_state(&self) -> State {
+        self.current_state
+    }
+
+    pub fn is_crouching(&self) -> bool {
+        self.input_state.crouch
+    }
+
+    pub fn get_position(&self) -> Vector2f {
+        self.sprite.nav.position
+    }
+
+    /// State, direction, animation, health and input flags, for the debug overlay.
+    pub fn debug_text(&self) -> String {
+        let flags = [
+            (self.input_state.left_move, "left"),
+            (self.input_state.right_move, "right"),
+            (self.input_state.crouch, "crouch"),
+            (self.hitbox_active, "hit"),
+            (self.cancellable, "cancel"),
+            (self.ground.is_some(), "jump"),
+        ];
+        let flags: Vec<&str> = flags.iter().filter(|(on, _)| *on).map(|(_, name)| *name).collect();
+        let animation = &self.sprite.animation;
+        let position = self.sprite.nav.position;
+        format!(
+            "{}\n{} {}\nstep {}/{} frame {}\nx {:.0} y {:.0}\nhealth {}\n{}",
+            self.name,
+            self.current_state,
+            self.current_direction,
+            animation.step,
+            animation.nb_frames,
+            self.sprite.current_frame(),
+            position.x,
+            position.y,
+            self.health,
+            flags.join(" ")
+        )
+    }
+
+    /// Applies a reloaded configuration to the animations, keeping the fighter where it is.
+    pub fn reload(&mut self, configuration: &CharacterConfiguration) {
+        for (state, s) in self.states.iter_mut() {
+            if let Some(action) = configuration.get_action_configuration(&state.to_string()) {
+                *s.get_animation_state_mut() = FighterAnimation::from_action(action);
+            }
+        }
+        let scale = configuration.sprite.display.scale;
+        self.sprite.display.scale = Vector2f::new(scale, scale);
+        self.is_new_state = true;
+    }
+
+    /// Sounds requested since the last call.
+    pub fn take_sounds(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.sounds)
+    }
+
+    pub fn draw(&self, window: &mut RenderWindow) {
+        self.sprite.draw(window);
     }
 
     pub fn get_speed(&self) -> Vector2f {
-        self.sprite.nav.speed.clone()
+        self.sprite.nav.speed
     }
 
     pub fn get_name(&self) -> &str {
@@ -137,7 +510,7 @@ impl<'a> Fighter<'a> {
     }
 }
 
-impl<'a> InputProcessor for Fighter<'a> {
+impl InputProcessor for Fighter {
     fn process_event(&mut self, e: sfml::window::Event) -> ResultEvent {
         match e {
             Event::KeyPressed {
@@ -145,20 +518,20 @@ impl<'a> InputProcessor for Fighter<'a> {
             } => ResultEvent::Menu,
             _ => {
                 let input_event = self.input_state.on_input(e);
-                let mut _state = self.states.get_mut(&self.current_state);
+                self.input_history.push(input_event, &self.input_state);
+                // a cancellable action reacts to the input like the idle state
+                let current_state = if self.cancellable { State::Idle } else { self.current_state };
+                let mut _state = self.states.get_mut(&current_state);
                 match _state {
                     Some(s) => {
-                        let action_result = s.on_event(input_event, &self.input_state);
+                        let mut action_result = s.on_event(input_event, &self.input_state);
+                        if self.cancellable && action_result.0 == State::Idle {
+                            action_result.0 = self.current_state;
+                        }
                         if action_result.0 != self.current_state
                             || action_result.1 != self.current_direction
                         {
-                            self.current_state = action_result.0;
-                            self.current_direction = action_result.1;
-                            println!(
-                                "change : state : {}, direction : {}",
-                                self.current_state, self.current_direction
-                            );
-                            self.is_new_state = true;
+                            self.change_state(action_result.0, action_result.1);
                         }
                         ResultEvent::Solo
                     }
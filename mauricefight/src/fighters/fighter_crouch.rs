@@ -1,4 +1,5 @@
 use crate::common::Direction;
+use crate::configuration::error::ConfigurationError;
 use crate::configuration::resources::GameResources;
 use crate::fighters::fighter_input::FighterInputEvent;
 use crate::fighters::fighter_input::FighterInputState;
@@ -6,22 +7,25 @@ use crate::fighters::fighter_state::FighterAnimation;
 use crate::fighters::fighter_state::FighterState;
 use crate::fighters::fighter_state::State;
 use crate::sprites::animated_sprite::AnimatedSprite;
-use sfml::graphics::RenderWindow;
 
 pub struct FighterCrouch {
     animation: FighterAnimation,
 }
 
 impl FighterCrouch {
-    pub fn new(name: &str, resources: &GameResources, direction: Direction) -> Self {
-        FighterCrouch {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterCrouch {
             animation: FighterAnimation::new(
                 resources,
                 name,
                 State::Crouch.to_string().as_str(),
                 direction,
-            ),
-        }
+            )?,
+        })
     }
 }
 
@@ -30,6 +34,10 @@ impl FighterState for FighterCrouch {
         &self.animation
     }
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
     fn on_event(
         &mut self,
         event: FighterInputEvent,
@@ -45,9 +53,8 @@ impl FighterState for FighterCrouch {
         &mut self,
         sprite: &mut AnimatedSprite,
         input_state: &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction) {
-        sprite.next_frame(input_state.direction, window);
+        sprite.next_frame(input_state.direction);
         (State::Crouch, input_state.direction)
     }
 }
@@ -57,15 +64,19 @@ pub struct FighterEndCrouch {
 }
 
 impl FighterEndCrouch {
-    pub fn new(name: &str, resources: &GameResources, direction: Direction) -> Self {
-        FighterEndCrouch {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterEndCrouch {
             animation: FighterAnimation::new(
                 resources,
                 name,
                 State::EndCrouch.to_string().as_str(),
                 direction,
-            ),
-        }
+            )?,
+        })
     }
 }
 
@@ -74,6 +85,10 @@ impl FighterState for FighterEndCrouch {
         &self.animation
     }
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
     fn on_event(
         &mut self,
         event: FighterInputEvent,
@@ -89,15 +104,11 @@ impl FighterState for FighterEndCrouch {
         &mut self,
         sprite: &mut AnimatedSprite,
         input_state: &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction) {
-        //println!("FRAME UPDATE");
-        let frame_res = sprite.next_frame(input_state.direction, window);
+        let frame_res = sprite.next_frame(input_state.direction);
         if frame_res.0 {
-            println!("----IDLE !");
             (State::Idle, input_state.direction)
         } else {
-            println!("----END CROUCHE !");
             (State::EndCrouch, input_state.direction)
         }
     }
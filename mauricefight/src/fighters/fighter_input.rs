@@ -1,9 +1,10 @@
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use sfml::window::Event;
 use crate::common::Direction;
 use sfml::window::Key;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FighterInputEvent {
     Nothing,
     Move,
@@ -30,6 +31,7 @@ impl fmt::Display for FighterInputEvent {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FighterInputState {
     pub direction : Direction,
     pub right_move : bool,
@@ -48,6 +50,14 @@ impl FighterInputState {
     }
 
     pub fn on_input(&mut self, e: sfml::window::Event) -> FighterInputEvent {
+        let input = self.read_input(e);
+        if input != FighterInputEvent::Nothing {
+            log::debug!("{} ({:?})", input, e);
+        }
+        input
+    }
+
+    fn read_input(&mut self, e: sfml::window::Event) -> FighterInputEvent {
         match e {
             Event::KeyPressed { code: Key::A, .. } => {
                 FighterInputEvent::HighKick
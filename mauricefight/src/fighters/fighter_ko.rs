@@ -0,0 +1,59 @@
+use crate::common::Direction;
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::resources::GameResources;
+use crate::fighters::fighter_input::FighterInputEvent;
+use crate::fighters::fighter_input::FighterInputState;
+use crate::fighters::fighter_state::FighterAnimation;
+use crate::fighters::fighter_state::FighterState;
+use crate::fighters::fighter_state::State;
+use crate::sprites::animated_sprite::AnimatedSprite;
+
+/// Fighter knocked out : plays its animation once, then stays on the last frame
+/// whatever the inputs.
+pub struct FighterKo {
+    animation: FighterAnimation,
+}
+
+impl FighterKo {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterKo {
+            animation: FighterAnimation::new(
+                resources,
+                name,
+                State::Ko.to_string().as_str(),
+                direction,
+            )?,
+        })
+    }
+}
+
+impl FighterState for FighterKo {
+    fn get_animation_state(&self) -> &FighterAnimation {
+        &self.animation
+    }
+
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
+    fn on_event(
+        &mut self,
+        _event: FighterInputEvent,
+        input_state: &FighterInputState,
+    ) -> (State, Direction, bool) {
+        (State::Ko, input_state.direction, false)
+    }
+
+    fn on_frame_update(
+        &mut self,
+        sprite: &mut AnimatedSprite,
+        input_state: &FighterInputState,
+    ) -> (State, Direction) {
+        sprite.next_frame(input_state.direction);
+        (State::Ko, input_state.direction)
+    }
+}
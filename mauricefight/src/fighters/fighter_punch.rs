@@ -1,4 +1,5 @@
 use crate::common::Direction;
+use crate::configuration::error::ConfigurationError;
 use crate::configuration::resources::GameResources;
 use crate::fighters::fighter_input::FighterInputEvent;
 use crate::fighters::fighter_input::FighterInputState;
@@ -6,22 +7,25 @@ use crate::fighters::fighter_state::FighterAnimation;
 use crate::fighters::fighter_state::FighterState;
 use crate::fighters::fighter_state::State;
 use crate::sprites::animated_sprite::AnimatedSprite;
-use sfml::graphics::RenderWindow;
 
 pub struct FighterLeftPunch {
     animation: FighterAnimation,
 }
 
 impl FighterLeftPunch {
-    pub fn new(name: &str, resources: &GameResources, direction: Direction) -> Self {
-        FighterLeftPunch {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterLeftPunch {
             animation: FighterAnimation::new(
                 resources,
                 name,
                 State::LeftPunch.to_string().as_str(),
                 direction,
-            ),
-        }
+            )?,
+        })
     }
 }
 
@@ -31,9 +35,13 @@ impl FighterState for FighterLeftPunch {
         &self.animation
     }
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
     fn on_event(
         &mut self,
-        event: super::fighter_input::FighterInputEvent,
+        _event: super::fighter_input::FighterInputEvent,
         input_state: &FighterInputState,
     ) -> (State, Direction, bool) {
         (State::LeftPunch, input_state.direction, false)
@@ -43,15 +51,11 @@ impl FighterState for FighterLeftPunch {
         &mut self,
         sprite: &mut AnimatedSprite,
         input_state: &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction) {
-        //println!("FRAME UPDATE");
-        let frame_res = sprite.next_frame(input_state.direction, window);
+        let frame_res = sprite.next_frame(input_state.direction);
         if frame_res.0 {
-            println!("----IDLE !");
             (State::Idle, input_state.direction)
         } else {
-            println!("----END CROUCHE !");
             (State::LeftPunch, input_state.direction)
         }
     }
@@ -62,15 +66,19 @@ pub struct FighterRightPunch {
 }
 
 impl FighterRightPunch {
-    pub fn new(name: &str, resources: &GameResources, direction: Direction) -> Self {
-        FighterRightPunch {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterRightPunch {
             animation: FighterAnimation::new(
                 resources,
                 name,
                 State::RightPunch.to_string().as_str(),
                 direction,
-            ),
-        }
+            )?,
+        })
     }
 }
 
@@ -79,9 +87,13 @@ impl FighterState for FighterRightPunch {
         &self.animation
     }
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
     fn on_event(
         &mut self,
-        event: FighterInputEvent,
+        _event: FighterInputEvent,
         input_state: &FighterInputState,
     ) -> (State, Direction, bool) {
         (State::LeftPunch, input_state.direction, true)
@@ -91,15 +103,11 @@ impl FighterState for FighterRightPunch {
         &mut self,
         sprite: &mut AnimatedSprite,
         input_state: &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction) {
-        //println!("FRAME UPDATE");
-        let frame_res = sprite.next_frame(input_state.direction, window);
+        let frame_res = sprite.next_frame(input_state.direction);
         if frame_res.0 {
-            println!("----IDLE !");
             (State::Idle, input_state.direction)
         } else {
-            println!("----END CROUCHE !");
             (State::LeftPunch, input_state.direction)
         }
     }
@@ -1,12 +1,14 @@
 use crate::common::Direction;
 use crate::fighters::fighter_input::FighterInputEvent;
 use crate::fighters::fighter_input::FighterInputState;
+use crate::configuration::configuration::{ActionConfiguration, AnimationEvent, FrameConfiguration, HitBoxConfiguration};
+use crate::configuration::error::{ConfigurationError, ValidationProblem};
 use crate::configuration::resources::GameResources;
 use crate::sprites::animated_sprite::AnimatedSprite;
-use sfml::graphics::RenderWindow;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum State {
     Idle,
     Crouch,
@@ -18,6 +20,19 @@ pub enum State {
     Ko,
 }
 
+impl State {
+    pub const ALL: [State; 8] = [
+        State::Idle,
+        State::Crouch,
+        State::EndCrouch,
+        State::Move,
+        State::HighKick,
+        State::LeftPunch,
+        State::RightPunch,
+        State::Ko,
+    ];
+}
+
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -27,7 +42,27 @@ impl fmt::Display for State {
             State::HighKick => write!(f, "highkick"),
             State::LeftPunch => write!(f, "leftpunch"),
             State::RightPunch => write!(f, "rightpunch"),
-            _ => write!(f, "idle"),
+            State::Ko => write!(f, "ko"),
+            State::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// Collision boxes of a step.
+#[derive(Clone, Debug, Default)]
+pub struct FrameBoxes {
+    /// body, head and legs
+    pub hurt: Vec<HitBoxConfiguration>,
+    pub hit: Option<HitBoxConfiguration>,
+    pub push: Option<HitBoxConfiguration>,
+}
+
+impl FrameBoxes {
+    fn from_frame(frame: &FrameConfiguration) -> Self {
+        FrameBoxes {
+            hurt: vec![frame.body.clone(), frame.head.clone(), frame.legs.clone()],
+            hit: frame.hit.clone(),
+            push: frame.push.clone(),
         }
     }
 }
@@ -37,6 +72,17 @@ pub struct FighterAnimation {
     pub nb_frames: i32,
     pub delay: i32,
     pub speed: f32,
+    pub sound: Option<String>,
+    /// health removed by the attack of the action
+    pub damage: i32,
+    /// sheet frame of each step, from `sprite_index`
+    pub steps: Vec<i32>,
+    /// hold duration of each step, 0 for `delay`
+    pub durations: Vec<i32>,
+    /// events raised when each step is reached
+    pub events: Vec<Vec<AnimationEvent>>,
+    /// collision boxes of each step
+    pub boxes: Vec<FrameBoxes>,
 }
 
 impl FighterAnimation {
@@ -44,33 +90,57 @@ impl FighterAnimation {
         resources: &GameResources,
         name: &str,
         action_name: &str,
-        direction: Direction,
-    ) -> Self {
-        println!("perso : {}, action :{}", name, action_name);
-        let action_config = resources
+        _direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        log::debug!("{} : loading action {}", name, action_name);
+        let character = resources
             .configuration
             .get_character(name)
-            .unwrap()
-            .get_action_configuration(action_name)
-            .unwrap();
+            .ok_or_else(|| unknown_character(name))?;
+        let action_config = character.get_action_configuration(action_name).ok_or_else(|| {
+            ConfigurationError::Invalid(vec![ValidationProblem {
+                path: format!("characters.{}.actions", character.name),
+                message: format!("missing action \"{}\"", action_name),
+            }])
+        })?;
+        Ok(FighterAnimation::from_action(action_config))
+    }
+
+    pub fn from_action(action_config: &ActionConfiguration) -> Self {
+        let frames = &action_config.sequence.frames;
         FighterAnimation {
             sprite_index: action_config.sequence.index,
             nb_frames: action_config.sequence.nb_frames,
             delay: action_config.sequence.delay,
             speed: action_config.sequence.speed,
+            sound: action_config.sound.clone(),
+            damage: action_config.damage,
+            steps: frames.iter().map(|frame| frame.count).collect(),
+            durations: frames.iter().map(|frame| frame.duration).collect(),
+            events: frames.iter().map(|frame| frame.events.clone()).collect(),
+            boxes: frames.iter().map(FrameBoxes::from_frame).collect(),
         }
     }
 }
 
+/// Error of a fighter created for a character the configuration doesn't declare.
+pub fn unknown_character(name: &str) -> ConfigurationError {
+    ConfigurationError::Invalid(vec![ValidationProblem {
+        path: String::from("characters"),
+        message: format!("unknown character \"{}\"", name),
+    }])
+}
+
 pub trait FighterState {
     fn on_event(&mut self, event : FighterInputEvent, input_state : &FighterInputState) -> (State, Direction, bool);
 
     fn get_animation_state(&self) -> &FighterAnimation;
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation;
+
     fn on_frame_update(
         &mut self,
         sprite: &mut AnimatedSprite, 
         input_state : &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction);
 }
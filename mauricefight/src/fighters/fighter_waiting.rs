@@ -1,4 +1,5 @@
 use crate::common::Direction;
+use crate::configuration::error::ConfigurationError;
 use crate::configuration::resources::GameResources;
 use crate::fighters::fighter_input::FighterInputEvent;
 use crate::fighters::fighter_input::FighterInputState;
@@ -6,22 +7,25 @@ use crate::fighters::fighter_state::FighterAnimation;
 use crate::fighters::fighter_state::FighterState;
 use crate::fighters::fighter_state::State;
 use crate::sprites::animated_sprite::AnimatedSprite;
-use sfml::graphics::RenderWindow;
 
 pub struct FighterWaiting {
     animation: FighterAnimation,
 }
 
 impl FighterWaiting {
-    pub fn new(name: &str, resources: &GameResources, direction: Direction) -> Self {
-        FighterWaiting {
+    pub fn new(
+        name: &str,
+        resources: &GameResources,
+        direction: Direction,
+    ) -> Result<Self, ConfigurationError> {
+        Ok(FighterWaiting {
             animation: FighterAnimation::new(
                 resources,
                 name,
                 State::Idle.to_string().as_str(),
                 direction,
-            ),
-        }
+            )?,
+        })
     }
 }
 
@@ -30,6 +34,10 @@ impl FighterState for FighterWaiting {
         &self.animation
     }
 
+    fn get_animation_state_mut(&mut self) -> &mut FighterAnimation {
+        &mut self.animation
+    }
+
     fn on_event(
         &mut self,
         event: FighterInputEvent,
@@ -49,10 +57,8 @@ impl FighterState for FighterWaiting {
         &mut self,
         sprite: &mut AnimatedSprite,
         input_state: &FighterInputState,
-        window: &mut RenderWindow,
     ) -> (State, Direction) {
-        //println!("FRAME UPDATE");
-        let frame_res = sprite.next_frame(input_state.direction, window);
+        let frame_res = sprite.next_frame(input_state.direction);
         if frame_res.0 {
             sprite.restart_animation();
         }
@@ -0,0 +1,76 @@
+use crate::fighters::fighter_input::{FighterInputEvent, FighterInputState};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of inputs kept by the history.
+const HISTORY_LENGTH: usize = 16;
+
+/// Directions held by a fighter, with the attack button pressed when they changed.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputEntry {
+    pub left: bool,
+    pub right: bool,
+    pub down: bool,
+    pub button: Option<FighterInputEvent>,
+    /// frames the entry lasted, or lasts for the newest one
+    pub frames: u32,
+}
+
+impl InputEntry {
+    fn same_directions(&self, other: &InputEntry) -> bool {
+        (self.left, self.right, self.down) == (other.left, other.right, other.down)
+    }
+}
+
+/// Recent logical inputs of a fighter, as consumed by its states.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputHistory {
+    /// newest first
+    entries: VecDeque<InputEntry>,
+}
+
+impl InputHistory {
+    pub fn new() -> Self {
+        InputHistory {
+            entries: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    /// Adds an input read by the fighter, when it presses a button or changes the directions held.
+    pub fn push(&mut self, event: FighterInputEvent, state: &FighterInputState) {
+        let button = match event {
+            FighterInputEvent::Nothing => return,
+            FighterInputEvent::HighKick | FighterInputEvent::LeftPunch | FighterInputEvent::RightPunch => {
+                Some(event)
+            }
+            _ => None,
+        };
+        let entry = InputEntry {
+            left: state.left_move,
+            right: state.right_move,
+            down: state.crouch,
+            button,
+            frames: 0,
+        };
+        let unchanged = self.entries.front().is_some_and(|x| x.same_directions(&entry));
+        if button.is_none() && unchanged {
+            return;
+        }
+        if self.entries.len() == HISTORY_LENGTH {
+            self.entries.pop_back();
+        }
+        self.entries.push_front(entry);
+    }
+
+    /// Counts a frame for the newest input.
+    pub fn tick(&mut self) {
+        if let Some(entry) = self.entries.front_mut() {
+            entry.frames = entry.frames.saturating_add(1);
+        }
+    }
+
+    /// Inputs from the newest one.
+    pub fn entries(&self) -> impl Iterator<Item = &InputEntry> {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,105 @@
+use crate::fighters::fighter::{BoxKind, Fighter};
+use crate::gui::text::{Align, BitmapFont};
+use crate::screen::GAME_WIDTH;
+use sfml::graphics::{Color, FloatRect, RectangleShape, RenderTarget, RenderWindow, Shape};
+use sfml::system::{Clock, Time, Vector2f};
+use sfml::SfBox;
+
+/// Scale of the game font in the overlays, a line of capitals about 4 pixels high.
+pub const TEXT_SCALE: f32 = 0.25;
+const FIGHTER_COLUMN_WIDTH: f32 = 90.;
+const ORIGIN_SIZE: f32 = 4.;
+
+/// Draws a small text with the game font, in the gui view.
+pub fn draw_text(
+    window: &mut RenderWindow,
+    font: &mut BitmapFont,
+    text: &str,
+    position: Vector2f,
+    align: Align,
+    color: Color,
+) {
+    font.set_scale(TEXT_SCALE);
+    font.set_color(color);
+    font.draw(window, text, position, align);
+}
+
+fn box_color(kind: BoxKind) -> Color {
+    match kind {
+        BoxKind::Hurt => Color::rgb(60, 140, 255),
+        BoxKind::Hit => Color::RED,
+        BoxKind::Push => Color::GREEN,
+    }
+}
+
+fn draw_box(window: &mut RenderWindow, area: FloatRect, color: Color) {
+    let mut shape = RectangleShape::from_rect(area);
+    shape.set_fill_color(Color::rgba(color.r, color.g, color.b, 50));
+    shape.set_outline_color(color);
+    shape.set_outline_thickness(-0.5);
+    window.draw(&shape);
+}
+
+/// Overlay toggled with F3 : collision boxes and pivot of the fighters, their
+/// state, animation and inputs, the frame rate and the time spent on a frame.
+pub struct DebugOverlay {
+    pub visible: bool,
+    clock: SfBox<Clock>,
+    fps: f32,
+    /// update and draw time of a frame, in milliseconds
+    tick: f32,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        DebugOverlay {
+            visible: false,
+            clock: Clock::start(),
+            fps: 0.,
+            tick: 0.,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        log::debug!("debug overlay {}", if self.visible { "shown" } else { "hidden" });
+    }
+
+    /// Measures a frame, `tick` being its update and draw time. The values are smoothed.
+    pub fn end_frame(&mut self, tick: Time) {
+        let elapsed = self.clock.restart().as_seconds();
+        if elapsed > 0. {
+            self.fps = self.fps * 0.9 + 0.1 / elapsed;
+        }
+        self.tick = self.tick * 0.9 + 0.1 * tick.as_seconds() * 1000.;
+    }
+
+    /// Draws the boxes and pivots of the fighters, in the game view.
+    pub fn draw_fighters(&self, window: &mut RenderWindow, fighters: &[Fighter]) {
+        for fighter in fighters {
+            for (kind, area) in fighter.collision_boxes() {
+                draw_box(window, area, box_color(kind));
+            }
+            let position = fighter.get_position();
+            let half = ORIGIN_SIZE / 2.;
+            draw_box(window, FloatRect::new(position.x - half, position.y - 0.25, ORIGIN_SIZE, 0.5), Color::WHITE);
+            draw_box(window, FloatRect::new(position.x - 0.25, position.y - half, 0.5, ORIGIN_SIZE), Color::WHITE);
+        }
+    }
+
+    /// Draws the texts, in the gui view. `simulation` describes the speed of the simulation.
+    pub fn draw_texts(
+        &self,
+        window: &mut RenderWindow,
+        font: &mut BitmapFont,
+        fighters: &[Fighter],
+        simulation: &str,
+    ) {
+        let timing = format!("fps {:.0}\ntick {:.1} ms\n{}", self.fps, self.tick, simulation);
+        draw_text(window, font, &timing, Vector2f::new(GAME_WIDTH - 2., 2.), Align::Right, Color::YELLOW);
+        for (i, fighter) in fighters.iter().enumerate() {
+            let position = Vector2f::new(2. + i as f32 * FIGHTER_COLUMN_WIDTH, 2.);
+            draw_text(window, font, &fighter.debug_text(), position, Align::Left, Color::WHITE);
+        }
+    }
+}
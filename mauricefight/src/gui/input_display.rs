@@ -0,0 +1,105 @@
+use crate::configuration::configuration::SheetFrame;
+use crate::configuration::resources::{self, GameResources};
+use crate::fighters::fighter::Fighter;
+use crate::fighters::fighter_input::FighterInputEvent;
+use crate::fighters::input_history::InputEntry;
+use crate::gui::debug;
+use crate::gui::text::{Align, BitmapFont};
+use crate::screen::GAME_WIDTH;
+use crate::sprites::textured_sprite::TexturedSprite;
+use sfml::graphics::{Color, IntRect, RenderTarget, RenderWindow};
+use sfml::system::Vector2f;
+
+/// Id of the icons in the `sprites` of the configuration. Its frames are :
+/// neutral, left, right, down, down left, down right, high kick, left punch, right punch.
+pub const INPUT_ICONS: &str = "input_icons";
+
+const TOP: f32 = 24.;
+const ROW_HEIGHT: f32 = 13.;
+const ICON_SIZE: f32 = 12.;
+/// width of the held duration, left of the icons
+const FRAMES_WIDTH: f32 = 14.;
+const COLUMN_WIDTH: f32 = FRAMES_WIDTH + 2. * ICON_SIZE;
+const ROWS: usize = 10;
+
+fn direction_icon(entry: &InputEntry) -> usize {
+    match (entry.left && !entry.right, entry.right && !entry.left, entry.down) {
+        (true, _, false) => 1,
+        (_, true, false) => 2,
+        (false, false, true) => 3,
+        (true, _, true) => 4,
+        (_, true, true) => 5,
+        _ => 0,
+    }
+}
+
+fn button_icon(button: FighterInputEvent) -> Option<usize> {
+    match button {
+        FighterInputEvent::HighKick => Some(6),
+        FighterInputEvent::LeftPunch => Some(7),
+        FighterInputEvent::RightPunch => Some(8),
+        _ => None,
+    }
+}
+
+/// Text drawn for an icon when the configuration has no input icons.
+const ICON_TEXTS: [&str; 9] = ["", "<", ">", "v", "v<", "v>", "k", "l", "r"];
+
+/// Overlay listing the recent inputs of each fighter, the player one on the
+/// left and the other one on the right, with the frames each was held.
+pub struct InputDisplay {
+    icons: Option<(TexturedSprite, Vec<SheetFrame>)>,
+}
+
+impl InputDisplay {
+    pub fn new(resources: &GameResources) -> Self {
+        let icons = match resources.configuration.sprites.get(INPUT_ICONS) {
+            Some(sprite) => Some((
+                resources.get_sprite(&resources::sprite_texture(INPUT_ICONS)),
+                sprite.sheet_frames(),
+            )),
+            None => {
+                log::info!("no \"{}\" sprite, inputs are displayed as text", INPUT_ICONS);
+                None
+            }
+        };
+        InputDisplay { icons }
+    }
+
+    fn draw_icon(&mut self, window: &mut RenderWindow, font: &mut BitmapFont, icon: usize, position: Vector2f) {
+        match &mut self.icons {
+            Some((sprite, frames)) => {
+                if let Some(frame) = frames.get(icon) {
+                    sprite.set_texture_rect(IntRect::new(frame.left, frame.top, frame.width, frame.height));
+                    sprite.set_position(position);
+                    window.draw(sprite);
+                }
+            }
+            None => {
+                let position = position + Vector2f::new(2., 1.);
+                debug::draw_text(window, font, ICON_TEXTS[icon], position, Align::Left, Color::WHITE)
+            }
+        }
+    }
+
+    /// Draws the histories of the first two fighters, in the gui view.
+    pub fn draw(&mut self, window: &mut RenderWindow, font: &mut BitmapFont, fighters: &[Fighter]) {
+        for (i, fighter) in fighters.iter().take(2).enumerate() {
+            let left = if i == 0 { 2. } else { GAME_WIDTH - COLUMN_WIDTH - 2. };
+            for (row, entry) in fighter.input_history().entries().take(ROWS).enumerate() {
+                let y = TOP + row as f32 * ROW_HEIGHT;
+                let color = if row == 0 { Color::YELLOW } else { Color::WHITE };
+                let frames = entry.frames.min(99).to_string();
+                debug::draw_text(window, font, &frames, Vector2f::new(left, y + 1.), Align::Left, color);
+                let x = left + FRAMES_WIDTH;
+                let direction = direction_icon(entry);
+                if direction != 0 || entry.button.is_none() {
+                    self.draw_icon(window, font, direction, Vector2f::new(x, y));
+                }
+                if let Some(icon) = entry.button.and_then(button_icon) {
+                    self.draw_icon(window, font, icon, Vector2f::new(x + ICON_SIZE, y));
+                }
+            }
+        }
+    }
+}
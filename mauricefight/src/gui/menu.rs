@@ -1,225 +1,348 @@
 use crate::common::Direction;
-use crate::common::InputProcessor;
-use crate::common::ResultEvent;
-use crate::{configuration::resources::GameResources, sprites::animated_sprite::AnimatedSprite};
-use sfml::window::{Event, Key};
-use 
+use crate::audio::{SOUND_MENU_CONFIRM, SOUND_MENU_MOVE};
+use crate::configuration::configuration::{MenuAction, MenuConfiguration, MenuItemKind};
+use crate::configuration::settings::Settings;
+use crate::configuration::resources::{GameResources, BACKGROUND_TEXTURE, CURSOR_TEXTURE};
+use crate::sprites::animated_sprite::AnimatedSprite;
+use crate::sprites::textured_sprite::TexturedSprite;
+use crate::gui::text::{Align, BitmapFont};
+use crate::screen::GAME_WIDTH;
+use crate::time_control::TimeControl;
+use sfml::window::{mouse, Event, Key};
+use
    sfml::{
         graphics::{
-            RenderTarget, RenderWindow, Sprite,IntRect,
-            Transformable,
+            Color, RectangleShape, RenderTarget, RenderWindow, Shape, Transformable,
         },
         system::Vector2f,
     };
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-enum MenuAction {
+const TITLE_Y: f32 = 15.;
+const VALUE_X: f32 = 290.;
+const CURSOR_OFFSET: f32 = 25.;
+const SLIDER_WIDTH: f32 = 60.;
+const SLIDER_HEIGHT: f32 = 8.;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuEvent {
     Nothing,
-    Cancel,
-    Solo,
-    Multi,
-    Params
+    /// an action item was selected
+    Action(MenuAction, Option<String>),
+    /// a toggle, choice or slider changed the settings
+    SettingsChanged,
+    /// back was requested on the first opened menu
+    Closed,
 }
 
-pub struct Cursor<'a> {
-    sprite : AnimatedSprite<'a>,
-    selected : i32,
-    step : f32,
-    min : Vector2f,
-    max : Vector2f,
+pub struct Cursor {
+    sprite : AnimatedSprite,
 }
 
-impl<'a> Cursor<'a> {
-    pub fn new(resources : &'a GameResources) -> Self {
+impl Cursor {
+    pub fn new(resources : &GameResources) -> Self {
         Cursor {
             sprite : AnimatedSprite::new(
                 resources,
-                "cursor", 
-                resources.configuration.gui.cursor.sprite.size, 
-                resources.configuration.gui.cursor.sprite.display.scale, 
-                75.,
-                50.,
+                CURSOR_TEXTURE,
+                &resources.configuration.gui.cursor.sprite,
+                resources.configuration.gui.cursor.sprite.display.scale,
+                0.,
+                0.,
                 0.,
                 0.,
                 Direction::Right,
                 0,
                 resources.configuration.gui.cursor.delay,
                 resources.configuration.gui.cursor.sprite.nb_frames),
-            selected : 1,
-            step : 30.,
-            min : Vector2f::new(0., 48.),
-            max : Vector2f::new(75., 48. * 3.),
         }
     }
 
-    fn draw(&mut self, window : &mut RenderWindow) {
-        let frame_res = self.sprite.next_frame(Direction::Right, window);
+    pub fn set_position(&mut self, position : Vector2f) {
+        self.sprite.nav.position = position;
+    }
+
+    /// Advances the animation by a tick.
+    pub fn update(&mut self) {
+        let frame_res = self.sprite.next_frame(Direction::Right);
         if frame_res.0 {
             self.sprite.restart_animation();
         }
     }
 
-    fn move_up(&mut self) {
-        let y = self.sprite.nav.position.y - self.step;
-        if y  >= self.min.y {
-            self.sprite.nav.position.y = y;
-            self.selected = self.selected - 1;
+    pub fn draw(&self, window : &mut RenderWindow) {
+        self.sprite.draw(window);
+    }
+}
+
+/// Menu screens declared in `gui.menus`, navigated as a stack : submenus are
+/// pushed, back pops them.
+pub struct Menu {
+    menus : Vec<MenuConfiguration>,
+    /// opened menus with their selected item
+    stack : Vec<(usize, usize)>,
+    background : TexturedSprite,
+    fonts : BitmapFont,
+    cursor : Cursor,
+    /// ticks of the cursor animation, at the normal speed whatever the fight's one
+    time : TimeControl,
+    pub settings : Settings,
+    settings_changed : bool,
+    sound : Option<&'static str>,
+}
+
+impl Menu {
+    pub fn new(resources : &GameResources, settings : Settings, id : &str) -> Self {
+        let mut menu = Menu {
+            menus : resources.configuration.gui.menus.clone(),
+            stack : Vec::new(),
+            background : resources.get_sprite(BACKGROUND_TEXTURE),
+            fonts : BitmapFont::new(resources),
+            cursor : Cursor::new(resources),
+            time : TimeControl::new(1.),
+            settings,
+            settings_changed : false,
+            sound : None,
+        };
+        menu.open(id);
+        menu
+    }
+
+    /// Uses the menus and the font of a reloaded configuration, the opened menus staying open.
+    pub fn reload(&mut self, resources : &GameResources) {
+        let opened : Vec<(String, usize)> = self
+            .stack
+            .iter()
+            .map(|(index, selected)| (self.menus[*index].id.clone(), *selected))
+            .collect();
+        self.menus = resources.configuration.gui.menus.clone();
+        self.fonts = BitmapFont::new(resources);
+        self.stack.clear();
+        for (id, selected) in opened {
+            if let Some(index) = self.menus.iter().position(|x| x.id == id) {
+                let last = self.menus[index].items.len().saturating_sub(1);
+                self.stack.push((index, selected.min(last)));
+            }
+        }
+        if self.stack.is_empty() {
+            self.push("title");
         }
     }
 
-    fn move_down(&mut self) {
-        let y = self.sprite.nav.position.y + self.step;
-        if y <= self.max.y {
-            self.sprite.nav.position.y = y;
-            self.selected = self.selected + 1;
+    /// Replaces the opened menus by the menu `id`.
+    pub fn open(&mut self, id : &str) {
+        self.stack.clear();
+        self.push(id);
+    }
+
+    fn push(&mut self, id : &str) {
+        match self.menus.iter().position(|x| x.id == id) {
+            Some(index) => self.stack.push((index, 0)),
+            None => log::warn!("unknown menu {}", id),
         }
     }
-}
 
-pub struct Menu<'a> {
-    background : Sprite<'a>,
-    fonts : Sprite<'a>,
-    cursor : Cursor<'a>,
-}
+    fn current(&self) -> Option<(&MenuConfiguration, usize)> {
+        self.stack.last().map(|(index, selected)| (&self.menus[*index], *selected))
+    }
+
+    /// Kind of the selected item, None when the opened menu has no items.
+    fn selected_kind(&self) -> Option<&MenuItemKind> {
+        let (menu, selected) = self.current()?;
+        menu.items.get(selected).map(|item| &item.kind)
+    }
 
-impl<'a> Menu<'a> {
-    pub fn new(resources : &'a GameResources) -> Self { 
-        Menu {
-            background : resources.get_sprite("background"),
-            fonts : resources.get_sprite("fonts"),
-            cursor : Cursor::new(resources)
+    fn set_selected(&mut self, selected : usize) {
+        if let Some(last) = self.stack.last_mut() {
+            if last.1 != selected {
+                last.1 = selected;
+                self.sound = Some(SOUND_MENU_MOVE);
+            }
         }
     }
 
-    pub fn on_up(&mut self) {
-        self.cursor.move_up();
+    /// Sound effect requested by the last event.
+    pub fn take_sound(&mut self) -> Option<&'static str> {
+        self.sound.take()
     }
 
-    pub fn on_down(&mut self) {
-        self.cursor.move_down();
+    fn row_y(menu : &MenuConfiguration, row : usize) -> f32 {
+        menu.y + menu.step * row as f32
     }
 
-    fn select(&mut self) -> MenuAction {
-        match self.cursor.selected {
-            1 => MenuAction::Solo,
-            2 => MenuAction::Multi,
-            3 => MenuAction::Params,
-            4 => MenuAction::Cancel,
-            _ => MenuAction::Nothing,
+    /// Row of the item under a position in gui view coordinates.
+    fn item_at(&self, position : Vector2f) -> Option<usize> {
+        let (menu, _) = self.current()?;
+        if position.x < menu.x - CURSOR_OFFSET - 10. || position.x > VALUE_X {
+            return None;
+        }
+        let row = ((position.y - menu.y + 4.) / menu.step).floor();
+        if row >= 0. && (row as usize) < menu.items.len() {
+            Some(row as usize)
+        } else {
+            None
         }
     }
 
-    pub fn draw(&mut self, window : &mut RenderWindow, resources : &GameResources) {
-        self.fonts.set_scale(Vector2f::new(0.5, 0.5));
-        window.draw(&self.background);
-        self.cursor.draw(window);
-        self.print(window, "mode solo", Vector2f::new(100., 50.), resources, 10.);
-        self.print(window, "multijoueur", Vector2f::new(100., 80.), resources, 10.);
-        self.print(window, "parametres", Vector2f::new(100., 110.), resources, 10.);
-        self.print(window, "quitter", Vector2f::new(100., 140.), resources, 10.);
-    }
-
-    fn get_sprite_letter_index_from_char(&self, c : char) -> Option<i32> {
-        match c {
-            'a' => Option::from(0),
-            'b' => Option::from(1),
-            'c' => Option::from(2),
-            'd' => Option::from(3),
-            'e' => Option::from(4),
-            'f' => Option::from(5),
-            'g' => Option::from(6),
-            'h' => Option::from(7),
-            'i' => Option::from(8),
-            'j' => Option::from(9),
-            'k' => Option::from(10),
-            'l' => Option::from(11),
-            'm' => Option::from(12),
-            'n' => Option::from(13),
-            'o' => Option::from(14),
-            'p' => Option::from(15),
-            'q' => Option::from(16),
-            'r' => Option::from(17),
-            's' => Option::from(18),
-            't' => Option::from(19),
-            'u' => Option::from(20),
-            'v' => Option::from(21),
-            'w' => Option::from(22),
-            'x' => Option::from(23),
-            'y' => Option::from(24),
-            'z' => Option::from(25),
-            _=> Option::None,
-        }
-    }
-
-    fn print(&mut self, 
-        window : &mut RenderWindow, 
-        text : &str, 
-        mut position : Vector2f, 
-        resources : &GameResources,
-        tab : f32) {
-        for c in text.chars() { 
-            match self.get_sprite_letter_index_from_char(c) {
-                Some(index) => {
-                    let rect = IntRect::new(
-                        index * resources.configuration.gui.fonts.sprite.size,
-                        0,
-                        resources.configuration.gui.fonts.sprite.size,
-                        resources.configuration.gui.fonts.sprite.size,
-                    );
-                    //println!("POSITION {}", position.x);
-                    self.fonts.set_texture_rect(rect);
-                    self.fonts.set_position(position);
-                   
-                    window.draw(&self.fonts);
-                },
-                None => {
-                    //WHITESPACE
+    fn move_selection(&mut self, forward : bool) {
+        if let Some((menu, selected)) = self.current() {
+            let len = menu.items.len();
+            if len > 0 {
+                let next = if forward { selected + 1 } else { selected + len - 1 };
+                self.set_selected(next % len);
+            }
+        }
+    }
+
+    fn back(&mut self) -> MenuEvent {
+        if self.settings_changed {
+            if let Err(e) = self.settings.save() {
+                log::error!("unable to save settings : {}", e);
+            }
+            self.settings_changed = false;
+        }
+        if self.stack.len() > 1 {
+            self.stack.pop();
+            MenuEvent::Nothing
+        } else {
+            MenuEvent::Closed
+        }
+    }
+
+    /// Activates the selected item, `forward` gives the direction for choices and sliders.
+    fn activate(&mut self, forward : bool) -> MenuEvent {
+        let kind = match self.selected_kind() {
+            Some(kind) => kind.clone(),
+            None => return MenuEvent::Nothing,
+        };
+        self.sound = Some(SOUND_MENU_CONFIRM);
+        match kind {
+            MenuItemKind::Action { action, argument } => MenuEvent::Action(action, argument),
+            MenuItemKind::Submenu { menu } => {
+                self.push(&menu);
+                MenuEvent::Nothing
+            }
+            MenuItemKind::Toggle { setting }
+            | MenuItemKind::Choice { setting }
+            | MenuItemKind::Slider { setting } => {
+                if self.settings.change(&setting, forward) {
+                    self.settings_changed = true;
+                    MenuEvent::SettingsChanged
+                } else {
+                    log::warn!("unknown setting {}", setting);
+                    MenuEvent::Nothing
                 }
             }
-            position.x = position.x + tab;
+            MenuItemKind::Back => self.back(),
         }
     }
-}
 
-impl<'a> InputProcessor for Menu<'a> {
-    fn process_event(&mut self, e: Event) -> ResultEvent {
-        let mut res = ResultEvent::Menu;
+    /// Changes the selected setting with left/right, other items ignore it.
+    fn adjust(&mut self, forward : bool) -> MenuEvent {
+        match self.selected_kind() {
+            Some(MenuItemKind::Toggle { .. } | MenuItemKind::Choice { .. } | MenuItemKind::Slider { .. }) => {
+                self.activate(forward)
+            }
+            _ => MenuEvent::Nothing,
+        }
+    }
+
+    pub fn on_mouse_move(&mut self, position : Vector2f) {
+        if let Some(row) = self.item_at(position) {
+            self.set_selected(row);
+        }
+    }
+
+    pub fn on_click(&mut self, position : Vector2f, button : mouse::Button) -> MenuEvent {
+        match self.item_at(position) {
+            Some(row) => {
+                self.set_selected(row);
+                self.activate(button != mouse::Button::Right)
+            }
+            None => MenuEvent::Nothing,
+        }
+    }
+
+    pub fn process_event(&mut self, e: Event) -> MenuEvent {
         match e {
             Event::KeyPressed {
                 code: Key::Down, ..
             } => {
-                println!("KEY PUSH:DOWN");
-                self.on_down();
+                self.move_selection(true);
+                MenuEvent::Nothing
             }
             Event::KeyPressed {
                 code: Key::Up, ..
             } => {
-                println!("KEY PUSH:DOWN");
-                self.on_up();
+                self.move_selection(false);
+                MenuEvent::Nothing
             }
+            Event::KeyPressed {
+                code: Key::Right, ..
+            } => self.adjust(true),
+            Event::KeyPressed {
+                code: Key::Left, ..
+            } => self.adjust(false),
             Event::KeyPressed {
                 code: Key::Enter, ..
-            } => {
-                println!("KEY PUSH:Enter");
-                let action = self.select();
-                match action {
-                    MenuAction::Cancel => {
-                        res = ResultEvent::Exit
-                    }
-                    MenuAction::Solo => {
-                        res = ResultEvent::Solo
-                    }
-                    _ => {}
-                }
-            }
+            } => self.activate(true),
             Event::KeyPressed {
                 code: Key::Escape, ..
-            } => {
-                println!("KEY PUSH:ESC");
-                res = ResultEvent::Exit
+            } => self.back(),
+            _ => MenuEvent::Nothing,
+        }
+    }
+
+    fn draw_slider(window : &mut RenderWindow, ratio : f32, y : f32) {
+        let mut bar = RectangleShape::with_size(Vector2f::new(SLIDER_WIDTH, SLIDER_HEIGHT));
+        bar.set_position(Vector2f::new(VALUE_X - SLIDER_WIDTH, y + 4.));
+        bar.set_fill_color(Color::TRANSPARENT);
+        bar.set_outline_color(Color::WHITE);
+        bar.set_outline_thickness(1.);
+        window.draw(&bar);
+        bar.set_size(Vector2f::new(SLIDER_WIDTH * ratio.clamp(0., 1.), SLIDER_HEIGHT));
+        bar.set_fill_color(Color::YELLOW);
+        bar.set_outline_thickness(0.);
+        window.draw(&bar);
+    }
+
+    /// Animates the cursor, `frame_time` being the milliseconds since the previous frame.
+    pub fn update(&mut self, frame_time : f32) {
+        for _ in 0..self.time.ticks(frame_time) {
+            self.cursor.update();
+        }
+    }
+
+    pub fn draw(&mut self, window : &mut RenderWindow) {
+        let (menu, selected) = match self.stack.last() {
+            Some((index, selected)) => (&self.menus[*index], *selected),
+            None => return,
+        };
+        self.fonts.set_scale(0.5);
+        if menu.background {
+            window.draw(&self.background);
+        }
+        self.cursor.set_position(Vector2f::new(menu.x - CURSOR_OFFSET, Menu::row_y(menu, selected)));
+        self.cursor.draw(window);
+        if let Some(title) = &menu.title {
+            self.fonts.set_color(Color::WHITE);
+            self.fonts.draw(window, title, Vector2f::new(GAME_WIDTH / 2., TITLE_Y), Align::Center);
+        }
+        for (i, item) in menu.items.iter().enumerate() {
+            let y = Menu::row_y(menu, i);
+            let color = if i == selected { Color::YELLOW } else { Color::WHITE };
+            self.fonts.set_color(color);
+            self.fonts.draw(window, &item.label, Vector2f::new(menu.x, y), Align::Left);
+            match &item.kind {
+                MenuItemKind::Slider { setting } => {
+                    Menu::draw_slider(window, self.settings.ratio(setting).unwrap_or(0.), y);
+                }
+                MenuItemKind::Toggle { setting } | MenuItemKind::Choice { setting } => {
+                    if let Some(value) = self.settings.value_label(setting) {
+                        self.fonts.draw(window, &value, Vector2f::new(VALUE_X, y), Align::Right);
+                    }
+                }
+                _ => {}
             }
-            _ => {}
         }
-        res
     }
-}
\ No newline at end of file
+}
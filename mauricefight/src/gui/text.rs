@@ -0,0 +1,131 @@
+use crate::configuration::configuration::SheetFrame;
+use crate::configuration::resources::{GameResources, FONTS_TEXTURE};
+use crate::sprites::textured_sprite::TexturedSprite;
+use sfml::{
+    graphics::{Color, IntRect, RenderTarget, RenderWindow},
+    system::Vector2f,
+};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+struct Glyph {
+    index: i32,
+    width: f32,
+}
+
+/// Replaces french accented letters by their base letter, for sheets without accents.
+fn strip_accent(c: char) -> char {
+    match c {
+        'à' | 'â' | 'ä' => 'a',
+        'ç' => 'c',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'î' | 'ï' => 'i',
+        'ô' | 'ö' => 'o',
+        'ù' | 'û' | 'ü' => 'u',
+        'ÿ' => 'y',
+        'À' | 'Â' | 'Ä' => 'A',
+        'Ç' => 'C',
+        'É' | 'È' | 'Ê' | 'Ë' => 'E',
+        'Î' | 'Ï' => 'I',
+        'Ô' | 'Ö' => 'O',
+        'Ù' | 'Û' | 'Ü' => 'U',
+        _ => c,
+    }
+}
+
+/// Text drawn with the sprite sheet font declared in `gui.fonts`.
+pub struct BitmapFont {
+    sprite: TexturedSprite,
+    glyphs: HashMap<char, Glyph>,
+    frames: Vec<SheetFrame>,
+    space: f32,
+    line_height: f32,
+    scale: f32,
+}
+
+impl BitmapFont {
+    pub fn new(resources: &GameResources) -> Self {
+        let fonts = &resources.configuration.gui.fonts;
+        let descriptor = fonts.font.clone().unwrap_or_default();
+        let advance = descriptor.advance.unwrap_or(fonts.sprite.cell_width() as f32);
+        let glyphs = descriptor
+            .glyphs
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                let width = descriptor.widths.get(&c).copied().unwrap_or(advance);
+                (c, Glyph { index: index as i32, width })
+            })
+            .collect();
+        BitmapFont {
+            sprite: resources.get_sprite(FONTS_TEXTURE),
+            glyphs,
+            frames: fonts.sprite.sheet_frames(),
+            space: descriptor.space.unwrap_or(advance),
+            line_height: descriptor.line_height.unwrap_or(fonts.sprite.cell_height() as f32),
+            scale: 1.,
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        self.sprite.set_scale(Vector2f::new(scale, scale));
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.sprite.set_color(color);
+    }
+
+    /// Finds the glyph of a character, falling back to the other case
+    /// then to the letter without accent.
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        let base = strip_accent(c);
+        [c, base]
+            .iter()
+            .flat_map(|c| [*c, c.to_ascii_lowercase(), c.to_ascii_uppercase()])
+            .find_map(|c| self.glyphs.get(&c))
+    }
+
+    fn advance(&self, c: char) -> f32 {
+        self.glyph(c).map_or(self.space, |glyph| glyph.width) * self.scale
+    }
+
+    /// Width of a single line of text, in view coordinates.
+    pub fn line_width(&self, line: &str) -> f32 {
+        line.chars().map(|c| self.advance(c)).sum()
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height * self.scale
+    }
+
+    /// Draws a text, one line per '\n'. `position` is the top of the first line,
+    /// on its left side, center or right side according to `align`.
+    pub fn draw(&mut self, window: &mut RenderWindow, text: &str, position: Vector2f, align: Align) {
+        let mut y = position.y;
+        for line in text.lines() {
+            let mut x = match align {
+                Align::Left => position.x,
+                Align::Center => position.x - self.line_width(line) / 2.,
+                Align::Right => position.x - self.line_width(line),
+            };
+            for c in line.chars() {
+                let frame = self.glyph(c).and_then(|glyph| self.frames.get(glyph.index as usize));
+                if let Some(frame) = frame {
+                    self.sprite
+                        .set_texture_rect(IntRect::new(frame.left, frame.top, frame.width, frame.height));
+                    self.sprite.set_position(Vector2f::new(x, y));
+                    window.draw(&self.sprite);
+                }
+                x += self.advance(c);
+            }
+            y += self.line_height();
+        }
+    }
+}
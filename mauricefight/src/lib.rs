@@ -0,0 +1,3 @@
+//! Parts of the game shared with the tools, like the asset archive written by mauricefight-pack.
+
+pub mod archive;
@@ -0,0 +1,83 @@
+use crate::assets::Assets;
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::resources;
+use sfml::graphics::Image;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// Image decoded by the loading thread.
+pub struct DecodedImage(Image);
+
+// SAFETY: an sfImage is a pixel array in memory, not bound to a thread or an
+// OpenGL context, and it is owned by one thread at a time.
+unsafe impl Send for DecodedImage {}
+
+impl DecodedImage {
+    pub fn image(&self) -> &Image {
+        &self.0
+    }
+}
+
+/// Decodes the images of textures on a background thread. The textures are
+/// created from them on the main thread, which owns the OpenGL context.
+pub struct ImageLoader {
+    receiver: Receiver<(String, Result<DecodedImage, ConfigurationError>)>,
+    total: usize,
+    received: usize,
+    finished: bool,
+}
+
+impl ImageLoader {
+    /// Starts decoding `files`, given as texture id and image path.
+    pub fn start(assets: Arc<Assets>, files: Vec<(String, String)>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let total = files.len();
+        thread::spawn(move || {
+            for (id, path) in files {
+                let image = resources::decode_image(&assets, &path).map(DecodedImage);
+                if sender.send((id, image)).is_err() {
+                    // the loading was abandoned
+                    break;
+                }
+            }
+        });
+        ImageLoader {
+            receiver,
+            total,
+            received: 0,
+            finished: total == 0,
+        }
+    }
+
+    /// Images decoded since the last call, with their texture id.
+    pub fn poll(&mut self) -> Vec<(String, Result<DecodedImage, ConfigurationError>)> {
+        let mut images = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(image) => images.push(image),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        self.received += images.len();
+        images
+    }
+
+    /// Part of the images received, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.received as f32 / self.total as f32
+        }
+    }
+
+    /// All the images were received, or the loading thread stopped.
+    pub fn is_finished(&self) -> bool {
+        self.finished || self.received >= self.total
+    }
+}
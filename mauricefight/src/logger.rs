@@ -0,0 +1,119 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Prefix of the targets of the game messages, removed from the output.
+const CRATE_PREFIX: &str = "mauricefight::";
+
+/// Logger writing to stderr and optionally to a file. Levels are set per
+/// module with a spec like "warn,fighter=debug,menu=trace" : a directive applies
+/// to the modules with its name in their path, the deepest match wins.
+pub struct Logger {
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+    file: Option<Mutex<File>>,
+    start: Instant,
+}
+
+fn parse_level(value: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(value.trim()).map_err(|_| format!("unknown log level \"{}\"", value))
+}
+
+/// Raises a level by `steps` levels, down to trace.
+fn raise(level: LevelFilter, steps: u8) -> LevelFilter {
+    LevelFilter::iter()
+        .skip_while(|x| *x != level)
+        .nth(steps as usize)
+        .unwrap_or(LevelFilter::Trace)
+}
+
+impl Logger {
+    /// Reads a spec, `verbose` raising the default level and `quiet` keeping the errors only.
+    pub fn new(spec: &str, verbose: u8, quiet: bool) -> Result<Logger, String> {
+        let mut default = LevelFilter::Info;
+        let mut directives = Vec::new();
+        for directive in spec.split(',').filter(|x| !x.trim().is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => directives.push((module.trim().to_string(), parse_level(level)?)),
+                None => default = parse_level(directive)?,
+            }
+        }
+        default = if quiet { LevelFilter::Error } else { raise(default, verbose) };
+        Ok(Logger {
+            default,
+            directives,
+            file: None,
+            start: Instant::now(),
+        })
+    }
+
+    /// Copies the messages to a file, replacing its content.
+    pub fn with_file(mut self, path: &str) -> Result<Logger, String> {
+        let file = File::create(path).map_err(|e| format!("unable to create log file {} : {}", path, e))?;
+        self.file = Some(Mutex::new(file));
+        Ok(self)
+    }
+
+    fn level(&self, target: &str) -> LevelFilter {
+        let segments: Vec<&str> = target.split("::").collect();
+        let mut best: Option<(usize, LevelFilter)> = None;
+        for (module, level) in &self.directives {
+            if let Some(depth) = segments.iter().rposition(|x| x == module) {
+                if best.is_none_or(|(best_depth, _)| depth >= best_depth) {
+                    best = Some((depth, *level));
+                }
+            }
+        }
+        best.map_or(self.default, |(_, level)| level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |max, level| max.max(level))
+    }
+
+    /// Installs the logger for the `log` macros.
+    pub fn install(self) -> Result<(), String> {
+        log::set_max_level(self.max_level());
+        log::set_boxed_logger(Box::new(self)).map_err(|e| e.to_string())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let target = record.target();
+        let line = format!(
+            "{:>9.3} {:<5} {} : {}",
+            self.start.elapsed().as_secs_f32(),
+            record.level(),
+            target.strip_prefix(CRATE_PREFIX).unwrap_or(target),
+            record.args()
+        );
+        eprintln!("{}", line);
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
@@ -1,29 +1,47 @@
 use configuration::resources;
-use sfml::SfBox;
-use std::{fs::File, borrow::Borrow};
-use std::collections::HashMap;
-use 
-    sfml::{
-        graphics::{
-            RenderTarget, RenderWindow, Texture,Sprite,IntRect,View,FloatRect,Image,
-            Transformable,
-        },
-        system::Vector2f,
-        window::{ContextSettings, Style},
-};
+use configuration::configuration::Configuration;
+use configuration::error::{ConfigurationError, ValidationProblem};
+use configuration::settings::Settings;
+use std::process;
+use clap::Parser;
+use std::net::SocketAddr;
+use std::time::Duration;
 
 mod configuration {
+    pub mod aseprite;
+    #[allow(clippy::module_inception)]
     pub mod configuration;
+    pub mod error;
+    pub mod inheritance;
+    pub mod loader;
     pub mod resources;
+    pub mod settings;
+    pub mod validation;
+    pub mod watcher;
 }
 mod arena;
+mod assets;
+mod audio;
+mod cli;
 mod engine;
+mod loading;
+mod logger;
+mod netplay;
+mod replay;
+mod save_state;
+mod screen;
+mod time_control;
+mod training;
 mod gui {
+    pub mod debug;
+    pub mod input_display;
     pub mod menu;
+    pub mod text;
 }
 mod common;
 mod sprites {
     pub  mod animated_sprite;
+    pub mod textured_sprite;
 }
 mod fighters {
     pub mod fighter_input;
@@ -33,92 +51,167 @@ mod fighters {
     pub mod fighter_move;
     pub mod fighter_crouch;
     pub mod fighter_high_kick;
+    pub mod fighter_ko;
     pub mod fighter;
+    pub mod input_history;
 }
 
-pub struct ScreenConfiguration {
-    pub view_size: Vector2f,
-    pub view_center: Vector2f,
-    pub ratio: f32,
-    pub aa_level: u32,
-    pub width: u32,
-    pub height: u32,
+fn option_error(option: &str, message: String) -> ConfigurationError {
+    ConfigurationError::Invalid(vec![ValidationProblem {
+        path: option.to_string(),
+        message,
+    }])
 }
 
-impl ScreenConfiguration {
-    pub fn new() -> Self {
-        ScreenConfiguration {
-            view_size: Vector2f::new(800., 600.),
-            view_center: Vector2f::new(400., 300.),
-            ratio: 2.7,
-            aa_level: 0,
-            width: 800,
-            height: 600,
+/// Name of a character of the configuration, as written in the configuration.
+fn character_name(configuration: &Configuration, option: &str, name: &str) -> Result<String, ConfigurationError> {
+    configuration
+        .get_character(name)
+        .map(|x| x.name.clone())
+        .ok_or_else(|| option_error(option, format!("unknown character \"{}\"", name)))
+}
+
+/// Installs the logger configured by the options, then by the settings.
+fn init_logger(options: &cli::Options, settings: &Settings) -> Result<(), String> {
+    let spec = options.log.as_deref().unwrap_or(&settings.log);
+    let logger = logger::Logger::new(spec, options.verbose, options.quiet)?;
+    let logger = match options.log_file.as_ref().or(settings.log_file.as_ref()) {
+        Some(path) => logger.with_file(path)?,
+        None => logger,
+    };
+    logger.install()
+}
+
+/// Session of the online versus requested by the options.
+fn online_session(options: &cli::Options) -> Result<Option<netplay::RollbackSession>, ConfigurationError> {
+    let (option, bind, peer, side) = match (options.host, options.join) {
+        (Some(port), _) => ("--host", SocketAddr::from(([0, 0, 0, 0], port)), None, 0),
+        (None, Some(address)) => {
+            let bind = match address {
+                SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+                SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+            };
+            ("--join", bind, Some(address), 1)
         }
-    }
+        (None, None) => return Ok(None),
+    };
+    let net_options = netplay::NetOptions {
+        bind,
+        peer,
+        side,
+        input_delay: options.input_delay,
+        latency: Duration::from_millis(options.net_latency),
+        loss: options.net_loss / 100.,
+    };
+    netplay::RollbackSession::new(net_options)
+        .map(Some)
+        .map_err(|e| option_error(option, format!("unable to open the UDP socket on {} : {}", bind, e)))
 }
 
+fn run(options: cli::Options, settings: Settings) -> Result<(), ConfigurationError> {
+    let assets = assets::Assets::locate(options.root.as_deref(), options.archive.as_deref(), &options.config)?;
+    log::info!("assets read from {}", assets);
 
-fn main() {
     // load configuration
-    let file = File::open("resources/configuration.json").unwrap();
-    let configuration: configuration::configuration::Configuration = serde_json::from_reader(file).unwrap();
-    let screen_configuration = ScreenConfiguration::new();
-    let textures = resources::load_textures(&configuration);
-    let game_resources = resources::GameResources::new(configuration, &textures);
+    let configuration = Configuration::load(&assets, &options.config)?;
+    log::info!("configuration read from {}", configuration.sources.join(", "));
+    let replay = match &options.replay {
+        Some(path) => Some(replay::Replay::load(path)?),
+        None => None,
+    };
 
-    // load levels sprites
-    let background_arena = Image::from_file(&game_resources.configuration.levels[0].sprite.img).unwrap();
-    let mut texture = Texture::new().unwrap();
-    texture
-        .load_from_image(
-            &background_arena,
-            IntRect::new(
-                0,
-                0,
-                background_arena.size().x.try_into().unwrap(),
-                background_arena.size().y.try_into().unwrap(),
-            ),
-        )
-        .unwrap();
-    texture.set_smooth(true);
-    let mut arena_sprite = Sprite::new();
-    arena_sprite.set_texture(&texture, true);
+    // fighters and level from the options, then from the replay
+    let stage = options.stage.clone().or_else(|| replay.as_ref().map(|x| x.level.clone()));
+    let level = match &stage {
+        Some(name) => configuration
+            .levels
+            .iter()
+            .position(|x| x.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| option_error("--stage", format!("unknown level \"{}\"", name)))?,
+        None => 0,
+    };
+    let replay_character = |i: usize| replay.as_ref().and_then(|x| x.characters.get(i).cloned());
+    let player1 = match options.player1.clone().or_else(|| replay_character(0)) {
+        Some(name) => character_name(&configuration, "--p1", &name)?,
+        None => configuration.characters[0].name.clone(),
+    };
+    let player2 = match options.player2.clone().or_else(|| replay_character(1)) {
+        Some(name) => character_name(&configuration, "--p2", &name)?,
+        None => player1.clone(),
+    };
+
+    let game_resources = resources::GameResources::new(configuration, assets);
+    if options.headless {
+        // the textures are loaded on first use, check their images now
+        let textures = game_resources.check_images()?;
+        let sounds = game_resources.load_sounds();
+        if !options.quiet {
+            let configuration = &game_resources.configuration;
+            println!(
+                "configuration ok : {} level(s), {} character(s), {} texture(s), {} sound(s)",
+                configuration.levels.len(),
+                configuration.characters.len(),
+                textures,
+                sounds
+            );
+        }
+        return Ok(());
+    }
 
-     
-    // load modules
-    let arena = arena::Arena::new(arena_sprite);
-    
     // create window
-    let context_settings = ContextSettings {
-        antialiasing_level: screen_configuration.aa_level,
-        ..Default::default()
-    };
-    let mut window = RenderWindow::new(
-        (screen_configuration.width, screen_configuration.height),
-        "Maurice 2D",
-        Style::CLOSE,
-        &context_settings,
-    );
-    window.set_framerate_limit(60);
-    window.set_vertical_sync_enabled(true);
-    let mut view = View::new(screen_configuration.view_center, screen_configuration.view_size);
-    view.set_viewport(FloatRect::new(0., 0., screen_configuration.ratio, screen_configuration.ratio));
-    window.set_view(&view);
+    let mut screen_configuration = screen::ScreenConfiguration::new(&settings);
+    if let Some((width, height)) = options.size {
+        screen_configuration.width = width;
+        screen_configuration.height = height;
+    }
+    if let Some(fullscreen) = options.fullscreen() {
+        screen_configuration.fullscreen = fullscreen;
+    }
+    let screen = screen::Screen::new(screen_configuration);
+    game_resources.load_sounds();
 
     let mut engine = engine::MauriceFight2dEngine::new(
-        window, 
-        view, 
-        arena,
-        &game_resources);
+        screen,
+        game_resources,
+        audio::Audio::new(&settings),
+        settings,
+        &options.config);
+    engine.set_level(level);
+    engine.set_speed(options.speed);
+    if let Some(replay) = &replay {
+        engine.play_replay(replay::ReplayPlayer::new(replay));
+    }
+    if let Some(path) = &options.record {
+        engine.record(path);
+    }
+    if let Some(session) = online_session(&options)? {
+        engine.play_online(session);
+    }
+    // online, both players go straight to the same fight
+    let mode = if options.is_online() { cli::StartMode::Versus } else { options.mode };
+    engine.start(mode, &[player1, player2]);
 
     // game loop
     loop {
-        while let Some(event) = engine.window.poll_event() {
-            if engine.process_input_event(event, &game_resources) {
-                return;
+        while let Some(event) = engine.screen.window.poll_event() {
+            if engine.process_input_event(event) {
+                engine.finish();
+                return Ok(());
             }
         }
-        engine.render_frame(&game_resources);
+        engine.render_frame();
+    }
+}
+
+fn main() {
+    let options = cli::Options::parse();
+    let settings = Settings::load();
+    if let Err(e) = init_logger(&options, &settings) {
+        eprintln!("{}", e);
+        process::exit(2);
+    }
+    if let Err(e) = run(options, settings) {
+        log::error!("{}", e);
+        process::exit(1);
     }
 }
@@ -0,0 +1,576 @@
+use crate::save_state::FightSnapshot;
+use sfml::window::{Event, Key};
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const DOWN: u8 = 4;
+const HIGH_KICK: u8 = 8;
+const LEFT_PUNCH: u8 = 16;
+const RIGHT_PUNCH: u8 = 32;
+const DIRECTIONS: u8 = LEFT | RIGHT | DOWN;
+/// Bits of the inputs and the keys the fighters read them from.
+const INPUT_KEYS: [(u8, Key); 6] = [
+    (LEFT, Key::Left),
+    (RIGHT, Key::Right),
+    (DOWN, Key::Down),
+    (HIGH_KICK, Key::A),
+    (LEFT_PUNCH, Key::B),
+    (RIGHT_PUNCH, Key::C),
+];
+
+/// Ticks simulated ahead of the last remote input received, the game waits beyond.
+const MAX_PREDICTION: u32 = 8;
+/// Most inputs sent in a packet, the oldest ones not acknowledged first.
+const MAX_PACKET_INPUTS: usize = 64;
+/// The peer is considered gone without packets for this long.
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAGIC: [u8; 2] = *b"MF";
+/// magic, fight id, acknowledged ticks, first tick, input count
+const HEADER_SIZE: usize = 2 + 4 + 4 + 4 + 1;
+
+/// Directions held and buttons pressed by a player during a tick.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TickInput(u8);
+
+impl TickInput {
+    /// Key events giving this input to a fighter which had `previous` at the
+    /// previous tick : directions pressed or released, buttons pressed.
+    pub fn events(self, previous: TickInput) -> Vec<Event> {
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        let mut events = Vec::new();
+        for (bit, code) in INPUT_KEYS {
+            let (held, was_held) = (self.0 & bit != 0, previous.0 & bit != 0);
+            if held && (bit & DIRECTIONS == 0 || !was_held) {
+                events.push(Event::KeyPressed { code, alt, ctrl, shift, system });
+            } else if !held && was_held && bit & DIRECTIONS != 0 {
+                events.push(Event::KeyReleased { code, alt, ctrl, shift, system });
+            }
+        }
+        events
+    }
+
+    /// Input guessed for the next ticks : the same directions, without button.
+    fn predicted(self) -> TickInput {
+        TickInput(self.0 & DIRECTIONS)
+    }
+}
+
+/// Keyboard state of the local player, read once per tick.
+#[derive(Default)]
+pub struct LocalInput {
+    held: u8,
+    /// buttons pressed since the last tick
+    pressed: u8,
+}
+
+impl LocalInput {
+    pub fn on_event(&mut self, e: Event) {
+        let (code, down) = match e {
+            Event::KeyPressed { code, .. } => (code, true),
+            Event::KeyReleased { code, .. } => (code, false),
+            _ => return,
+        };
+        if let Some((bit, _)) = INPUT_KEYS.iter().find(|(_, key)| *key == code) {
+            match (down, bit & DIRECTIONS != 0) {
+                (true, true) => self.held |= bit,
+                (false, true) => self.held &= !bit,
+                (true, false) => self.pressed |= bit,
+                (false, false) => {}
+            }
+        }
+    }
+
+    /// Releases everything, when the keyboard is used by a menu.
+    pub fn clear(&mut self) {
+        self.held = 0;
+        self.pressed = 0;
+    }
+
+    fn take(&mut self) -> TickInput {
+        TickInput(self.held | std::mem::take(&mut self.pressed))
+    }
+}
+
+/// Degrades the outgoing packets to test online play on one computer.
+struct LinkConditioner {
+    latency: Duration,
+    /// probability to drop a packet, from 0 to 1
+    loss: f32,
+    queue: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl LinkConditioner {
+    fn push(&mut self, packet: Vec<u8>) {
+        if self.loss > 0. && rand::random::<f32>() < self.loss {
+            return;
+        }
+        self.queue.push_back((Instant::now() + self.latency, packet));
+    }
+
+    /// Packets whose delay is over.
+    fn due(&mut self) -> Vec<Vec<u8>> {
+        let now = Instant::now();
+        let mut packets = Vec::new();
+        while let Some((_, packet)) = self.queue.front().filter(|(time, _)| *time <= now) {
+            packets.push(packet.clone());
+            self.queue.pop_front();
+        }
+        packets
+    }
+}
+
+/// Identifies a fight from its characters and level, both players must have the same.
+pub fn fight_id(characters: &[String], level: &str) -> u32 {
+    // FNV-1a
+    let text = format!("{}|{}", characters.join("|"), level);
+    text.bytes()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+/// Options of an online fight.
+pub struct NetOptions {
+    /// local address the socket is bound to
+    pub bind: SocketAddr,
+    /// address of the other player, learned from its first packet when hosting
+    pub peer: Option<SocketAddr>,
+    /// index of the local fighter
+    pub side: usize,
+    /// ticks the local inputs are delayed by, to hide the latency
+    pub input_delay: u32,
+    pub latency: Duration,
+    /// part of the packets dropped, from 0 to 1
+    pub loss: f32,
+}
+
+/// Online versus with rollback : the inputs of each tick are exchanged over UDP,
+/// the missing remote ones are predicted, and when a prediction proves wrong
+/// the fight goes back to the snapshot of that tick and is simulated again.
+pub struct RollbackSession {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    /// index of the local fighter
+    pub side: usize,
+    fight_id: u32,
+    /// ticks the local inputs are delayed by
+    input_delay: u32,
+    pub local_input: LocalInput,
+    /// local inputs by tick, the first ones neutral for the input delay
+    local: Vec<TickInput>,
+    /// remote inputs received by tick, without gap
+    remote: Vec<TickInput>,
+    /// remote inputs used for each simulated tick, received or predicted
+    used_remote: Vec<TickInput>,
+    /// local inputs the peer has received
+    acknowledged: u32,
+    /// next tick to simulate
+    tick: u32,
+    /// earliest tick simulated with a wrong prediction
+    rollback: Option<u32>,
+    /// fight state at the start of the ticks not confirmed yet
+    snapshots: VecDeque<(u32, FightSnapshot)>,
+    last_received: Option<Instant>,
+    conditioner: LinkConditioner,
+}
+
+impl RollbackSession {
+    pub fn new(options: NetOptions) -> io::Result<Self> {
+        let socket = UdpSocket::bind(options.bind)?;
+        socket.set_nonblocking(true)?;
+        log::info!(
+            "online on {} as player {}, input delay {}",
+            socket.local_addr()?,
+            options.side + 1,
+            options.input_delay
+        );
+        Ok(RollbackSession {
+            socket,
+            peer: options.peer,
+            side: options.side,
+            fight_id: 0,
+            input_delay: options.input_delay,
+            local_input: LocalInput::default(),
+            local: vec![TickInput::default(); options.input_delay as usize],
+            remote: Vec::new(),
+            used_remote: Vec::new(),
+            acknowledged: 0,
+            tick: 0,
+            rollback: None,
+            snapshots: VecDeque::new(),
+            last_received: None,
+            conditioner: LinkConditioner {
+                latency: options.latency,
+                loss: options.loss,
+                queue: VecDeque::new(),
+            },
+        })
+    }
+
+    /// Starts a fight from tick 0, the peer must start the same one.
+    pub fn restart(&mut self, fight_id: u32) {
+        self.fight_id = fight_id;
+        self.local = vec![TickInput::default(); self.input_delay as usize];
+        self.remote.clear();
+        self.used_remote.clear();
+        self.acknowledged = 0;
+        self.tick = 0;
+        self.rollback = None;
+        self.snapshots.clear();
+        self.last_received = None;
+    }
+
+    /// A packet of the peer was received for this fight.
+    pub fn is_connected(&self) -> bool {
+        self.last_received.is_some()
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.last_received.is_some_and(|x| x.elapsed() > TIMEOUT)
+    }
+
+    /// The next tick can be simulated : the peer is there and not too far behind.
+    pub fn can_advance(&self) -> bool {
+        self.is_connected() && self.tick < self.remote.len() as u32 + MAX_PREDICTION
+    }
+
+    /// Reads the packets received, returns the tick to go back to if a prediction was wrong.
+    pub fn receive(&mut self) -> Option<u32> {
+        let mut buffer = [0u8; HEADER_SIZE + MAX_PACKET_INPUTS];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, address)) => self.read_packet(&buffer[..size], address),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // an unreachable peer is reported on some systems, it may not be started yet
+                Err(e) => {
+                    log::trace!("receive : {}", e);
+                    break;
+                }
+            }
+        }
+        self.rollback.take()
+    }
+
+    fn read_packet(&mut self, packet: &[u8], address: SocketAddr) {
+        let word = |offset: usize| u32::from_le_bytes(packet[offset..offset + 4].try_into().unwrap());
+        if packet.len() < HEADER_SIZE || packet[..2] != MAGIC {
+            return;
+        }
+        if word(2) != self.fight_id {
+            // the peer may still be in the previous fight, or have other characters or level
+            log::debug!("packet of another fight from {}", address);
+            return;
+        }
+        if self.peer.is_none() {
+            log::info!("player joined from {}", address);
+        }
+        self.peer = Some(address);
+        self.last_received = Some(Instant::now());
+        self.acknowledged = self.acknowledged.max(word(6));
+        let first = word(10);
+        let inputs = &packet[HEADER_SIZE..HEADER_SIZE + (packet[14] as usize).min(packet.len() - HEADER_SIZE)];
+        for (i, input) in inputs.iter().enumerate() {
+            let tick = first + i as u32;
+            if tick != self.remote.len() as u32 {
+                continue;
+            }
+            let input = TickInput(*input);
+            if self.used_remote.get(tick as usize).is_some_and(|used| *used != input) {
+                self.rollback = Some(self.rollback.map_or(tick, |x| x.min(tick)));
+            }
+            self.remote.push(input);
+        }
+    }
+
+    /// Sends the local inputs not acknowledged, with the remote ones received.
+    pub fn send(&mut self) {
+        let peer = match self.peer {
+            Some(peer) => peer,
+            None => return,
+        };
+        let first = (self.acknowledged as usize).min(self.local.len());
+        let inputs = &self.local[first..self.local.len().min(first + MAX_PACKET_INPUTS)];
+        let mut packet = Vec::with_capacity(HEADER_SIZE + inputs.len());
+        packet.extend_from_slice(&MAGIC);
+        packet.extend_from_slice(&self.fight_id.to_le_bytes());
+        packet.extend_from_slice(&(self.remote.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&(first as u32).to_le_bytes());
+        packet.push(inputs.len() as u8);
+        packet.extend(inputs.iter().map(|x| x.0));
+        self.conditioner.push(packet);
+        for packet in self.conditioner.due() {
+            if let Err(e) = self.socket.send_to(&packet, peer) {
+                log::trace!("send : {}", e);
+            }
+        }
+    }
+
+    /// Next tick to simulate.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Reads the local input for the tick the delay applies to.
+    pub fn read_local_input(&mut self) {
+        let input = self.local_input.take();
+        self.local.push(input);
+    }
+
+    /// Inputs of the fighters at a tick, with the ones applied at the previous tick,
+    /// the ticks being simulated in order.
+    pub fn inputs(&mut self, tick: u32) -> ([TickInput; 2], [TickInput; 2]) {
+        let index = tick as usize;
+        let remote = match self.remote.get(index) {
+            Some(input) => *input,
+            None => self.remote.last().copied().unwrap_or_default().predicted(),
+        };
+        self.used_remote.truncate(index);
+        self.used_remote.push(remote);
+        let current = self.by_side(self.local.get(index).copied().unwrap_or_default(), remote);
+        let previous = match index.checked_sub(1) {
+            Some(i) => self.by_side(self.local[i], self.used_remote[i]),
+            None => [TickInput::default(); 2],
+        };
+        (current, previous)
+    }
+
+    /// Inputs of the two fighters from the local and the remote ones.
+    fn by_side(&self, local: TickInput, remote: TickInput) -> [TickInput; 2] {
+        match self.side {
+            0 => [local, remote],
+            _ => [remote, local],
+        }
+    }
+
+    /// Keeps the state at the start of a tick, until its inputs are confirmed.
+    pub fn save(&mut self, tick: u32, snapshot: FightSnapshot) {
+        while self.snapshots.back().is_some_and(|(x, _)| *x >= tick) {
+            self.snapshots.pop_back();
+        }
+        self.snapshots.push_back((tick, snapshot));
+        let confirmed = self.remote.len() as u32;
+        while self.snapshots.front().is_some_and(|(x, _)| *x < confirmed.min(tick)) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn snapshot(&self, tick: u32) -> Option<&FightSnapshot> {
+        self.snapshots.iter().find(|(x, _)| *x == tick).map(|(_, snapshot)| snapshot)
+    }
+
+    /// Counts a simulated tick.
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sfml::system::Vector2f;
+    use std::thread;
+
+    const FIGHT: u32 = 42;
+
+    fn session(side: usize, peer: Option<SocketAddr>) -> RollbackSession {
+        let mut session = RollbackSession::new(NetOptions {
+            bind: SocketAddr::from(([127, 0, 0, 1], 0)),
+            peer,
+            side,
+            input_delay: 2,
+            latency: Duration::ZERO,
+            loss: 0.,
+        })
+        .unwrap();
+        session.restart(FIGHT);
+        session
+    }
+
+    fn address(session: &RollbackSession) -> SocketAddr {
+        session.socket.local_addr().unwrap()
+    }
+
+    fn packet(fight_id: u32, ack: u32, first: u32, count: u8, inputs: &[u8]) -> Vec<u8> {
+        let mut packet = MAGIC.to_vec();
+        packet.extend_from_slice(&fight_id.to_le_bytes());
+        packet.extend_from_slice(&ack.to_le_bytes());
+        packet.extend_from_slice(&first.to_le_bytes());
+        packet.push(count);
+        packet.extend_from_slice(inputs);
+        packet
+    }
+
+    fn snapshot(tick: u64) -> FightSnapshot {
+        FightSnapshot {
+            level: String::from("arena"),
+            tick,
+            camera: Vector2f::new(0., 0.),
+            fighters: Vec::new(),
+            training: None,
+        }
+    }
+
+    fn key(e: &Event) -> (Key, bool) {
+        match *e {
+            Event::KeyPressed { code, .. } => (code, true),
+            Event::KeyReleased { code, .. } => (code, false),
+            _ => panic!("not a key event : {:?}", e),
+        }
+    }
+
+    /// Reads the packets of a session until a condition holds, the loopback being asynchronous.
+    fn receive_until(session: &mut RollbackSession, condition: impl Fn(&RollbackSession) -> bool) {
+        for _ in 0..200 {
+            session.receive();
+            if condition(session) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("nothing received");
+    }
+
+    #[test]
+    fn events_press_directions_once_and_buttons_every_tick() {
+        let left_kick = TickInput(LEFT | HIGH_KICK);
+        let events: Vec<_> = left_kick.events(TickInput::default()).iter().map(key).collect();
+        assert_eq!(events, [(Key::Left, true), (Key::A, true)]);
+        let events: Vec<_> = left_kick.events(left_kick).iter().map(key).collect();
+        assert_eq!(events, [(Key::A, true)]);
+        let events: Vec<_> = TickInput(DOWN).events(left_kick).iter().map(key).collect();
+        assert_eq!(events, [(Key::Left, false), (Key::Down, true)]);
+    }
+
+    #[test]
+    fn local_input_keeps_directions_and_takes_buttons() {
+        let mut input = LocalInput::default();
+        input.on_event(key_event(Key::Right, true));
+        input.on_event(key_event(Key::B, true));
+        input.on_event(key_event(Key::B, false));
+        assert_eq!(input.take(), TickInput(RIGHT | LEFT_PUNCH));
+        assert_eq!(input.take(), TickInput(RIGHT));
+        input.on_event(key_event(Key::Right, false));
+        assert_eq!(input.take(), TickInput::default());
+    }
+
+    fn key_event(code: Key, pressed: bool) -> Event {
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        if pressed {
+            Event::KeyPressed { code, alt, ctrl, shift, system }
+        } else {
+            Event::KeyReleased { code, alt, ctrl, shift, system }
+        }
+    }
+
+    #[test]
+    fn read_packet_ignores_invalid_packets() {
+        let mut session = session(0, None);
+        let from = SocketAddr::from(([127, 0, 0, 1], 9));
+        let mut bad_magic = packet(FIGHT, 0, 0, 1, &[LEFT]);
+        bad_magic[0] = b'X';
+        session.read_packet(&bad_magic, from);
+        session.read_packet(&packet(FIGHT + 1, 0, 0, 1, &[LEFT]), from);
+        session.read_packet(&packet(FIGHT, 0, 0, 1, &[LEFT])[..HEADER_SIZE - 1], from);
+        assert!(session.remote.is_empty());
+        assert!(!session.is_connected());
+        assert_eq!(session.peer, None);
+    }
+
+    #[test]
+    fn read_packet_keeps_remote_inputs_in_order() {
+        let mut session = session(0, None);
+        let from = SocketAddr::from(([127, 0, 0, 1], 9));
+        // the count is larger than the inputs of the packet
+        session.read_packet(&packet(FIGHT, 0, 0, 5, &[0, LEFT]), from);
+        assert_eq!(session.remote, [TickInput(0), TickInput(LEFT)]);
+        assert_eq!(session.peer, Some(from));
+        assert!(session.is_connected());
+        // a gap, then inputs already received and new ones
+        session.read_packet(&packet(FIGHT, 0, 3, 1, &[DOWN]), from);
+        assert_eq!(session.remote.len(), 2);
+        session.read_packet(&packet(FIGHT, 3, 1, 2, &[RIGHT, DOWN]), from);
+        assert_eq!(session.remote, [TickInput(0), TickInput(LEFT), TickInput(DOWN)]);
+        assert_eq!(session.acknowledged, 3);
+    }
+
+    #[test]
+    fn mispredicted_remote_input_rolls_back() {
+        let mut session = session(1, None);
+        let from = SocketAddr::from(([127, 0, 0, 1], 9));
+        session.read_packet(&packet(FIGHT, 0, 0, 1, &[LEFT]), from);
+        for tick in 0..4 {
+            session.read_local_input();
+            let (current, previous) = session.inputs(tick);
+            // the remote fighter is the first one, its directions are repeated
+            assert_eq!(current[0], TickInput(LEFT));
+            if tick > 0 {
+                assert_eq!(previous[0], TickInput(LEFT));
+            }
+            session.advance();
+        }
+        // the prediction was right for tick 1, wrong for tick 2
+        session.read_packet(&packet(FIGHT, 0, 1, 3, &[LEFT, RIGHT, RIGHT]), from);
+        assert_eq!(session.rollback.take(), Some(2));
+        let (current, previous) = session.inputs(2);
+        assert_eq!((current[0], previous[0]), (TickInput(RIGHT), TickInput(LEFT)));
+        session.inputs(3);
+        // a remote button is never predicted
+        session.read_packet(&packet(FIGHT, 0, 4, 1, &[HIGH_KICK]), from);
+        assert_eq!(session.rollback.take(), None);
+        assert_eq!(session.inputs(4).0[0], TickInput(HIGH_KICK));
+        assert_eq!(session.inputs(5).0[0], TickInput(0));
+    }
+
+    #[test]
+    fn save_keeps_the_snapshots_not_confirmed() {
+        let mut session = session(0, None);
+        let from = SocketAddr::from(([127, 0, 0, 1], 9));
+        for tick in 0..3 {
+            session.save(tick, snapshot(tick as u64));
+        }
+        session.read_packet(&packet(FIGHT, 0, 0, 2, &[0, 0]), from);
+        for tick in 3..6 {
+            session.save(tick, snapshot(tick as u64));
+        }
+        let ticks: Vec<u32> = session.snapshots.iter().map(|(x, _)| *x).collect();
+        assert_eq!(ticks, [2, 3, 4, 5]);
+        assert!(session.snapshot(1).is_none());
+        // simulated again from tick 3, the later snapshots are replaced
+        session.save(3, snapshot(30));
+        let ticks: Vec<u32> = session.snapshots.iter().map(|(x, _)| *x).collect();
+        assert_eq!(ticks, [2, 3]);
+        assert_eq!(session.snapshot(3).map(|x| x.tick), Some(30));
+    }
+
+    #[test]
+    fn sessions_exchange_inputs_over_loopback() {
+        let mut host = session(0, None);
+        let mut join = session(1, Some(address(&host)));
+        assert!(!join.can_advance());
+        join.send();
+        receive_until(&mut host, |x| x.is_connected());
+        assert_eq!(host.peer, Some(address(&join)));
+        // the neutral inputs of the delay are sent at once
+        assert_eq!(host.remote, [TickInput(0); 2]);
+        host.local_input.on_event(key_event(Key::Left, true));
+        host.read_local_input();
+        host.send();
+        receive_until(&mut join, |x| x.remote.len() == 3);
+        assert_eq!(join.remote[2], TickInput(LEFT));
+        assert!(join.can_advance());
+        // the join acknowledges the inputs, the host stops sending them
+        join.send();
+        receive_until(&mut host, |x| x.acknowledged == 3);
+    }
+
+    #[test]
+    fn fight_id_depends_on_the_characters_and_the_level() {
+        let characters = [String::from("Maurice"), String::from("JeanJacques")];
+        let id = fight_id(&characters, "arena");
+        assert_eq!(id, fight_id(&characters, "arena"));
+        assert_ne!(id, fight_id(&characters, "beach"));
+        assert_ne!(id, fight_id(&[String::from("Maurice"), String::from("Maurice")], "arena"));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::assets::Assets;
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::loader;
+use serde_derive::{Deserialize, Serialize};
+use sfml::window::{Event, Key};
+use std::fs::File;
+use std::io;
+
+/// A key pressed or released by the player, after `tick` simulation ticks of the fight.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ReplayEvent {
+    pub tick: u32,
+    pub key: Key,
+    pub pressed: bool,
+}
+
+impl ReplayEvent {
+    fn to_event(self) -> Event {
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        let code = self.key;
+        if self.pressed {
+            Event::KeyPressed { code, alt, ctrl, shift, system }
+        } else {
+            Event::KeyReleased { code, alt, ctrl, shift, system }
+        }
+    }
+}
+
+/// Inputs of a fight with the characters and the level they were played with.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub characters: Vec<String>,
+    pub level: String,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Reads a replay file, in json, ron or toml, relative to the current directory.
+    pub fn load(path: &str) -> Result<Replay, ConfigurationError> {
+        loader::read(&Assets::current_directory(), path)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+}
+
+/// Gives back the events of a replay when their tick is reached, called once per tick.
+pub struct ReplayPlayer {
+    events: Vec<ReplayEvent>,
+    next: usize,
+    /// ticks played
+    tick: u32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: &Replay) -> Self {
+        ReplayPlayer {
+            events: replay.events.clone(),
+            next: 0,
+            tick: 0,
+        }
+    }
+
+    /// Starts the replay again from its first event.
+    pub fn restart(&mut self) {
+        self.seek(0);
+    }
+
+    /// Goes to a tick of the replay, its events being the next ones given back.
+    pub fn seek(&mut self, tick: u32) {
+        self.tick = tick;
+        self.next = self.events.partition_point(|x| x.tick < tick);
+    }
+
+    /// Ticks since the replay started.
+    pub fn elapsed(&self) -> u32 {
+        self.tick
+    }
+
+    /// Events of the tick about to run, then counts it.
+    pub fn due_events(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(event) = self.events.get(self.next).filter(|x| x.tick <= self.tick) {
+            events.push(event.to_event());
+            self.next += 1;
+        }
+        self.tick += 1;
+        events
+    }
+}
+
+/// Records the key events of a fight, with the ticks run when they happen.
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    /// ticks recorded
+    tick: u32,
+}
+
+impl ReplayRecorder {
+    pub fn new() -> Self {
+        ReplayRecorder {
+            replay: Replay::default(),
+            tick: 0,
+        }
+    }
+
+    /// Forgets the recorded events, for a new fight.
+    pub fn restart(&mut self, characters: &[String], level: &str) {
+        self.replay = Replay {
+            characters: characters.to_vec(),
+            level: level.to_string(),
+            events: Vec::new(),
+        };
+        self.tick = 0;
+    }
+
+    /// Ticks since the recording started.
+    pub fn elapsed(&self) -> u32 {
+        self.tick
+    }
+
+    /// Counts a tick run, the next events being recorded after it.
+    pub fn tick(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        let (key, pressed) = match *event {
+            Event::KeyPressed { code, .. } => (code, true),
+            Event::KeyReleased { code, .. } => (code, false),
+            _ => return,
+        };
+        self.replay.events.push(ReplayEvent { tick: self.tick, key, pressed });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let event = |tick, key, pressed| ReplayEvent { tick, key, pressed };
+        Replay {
+            characters: vec![String::from("Maurice")],
+            level: String::from("arena1"),
+            events: vec![
+                event(0, Key::Right, true),
+                event(2, Key::Right, false),
+                event(2, Key::A, true),
+                event(5, Key::A, false),
+            ],
+        }
+    }
+
+    fn counts(player: &mut ReplayPlayer, ticks: u32) -> Vec<usize> {
+        (0..ticks).map(|_| player.due_events().len()).collect()
+    }
+
+    #[test]
+    fn gives_the_events_at_their_tick() {
+        let mut player = ReplayPlayer::new(&replay());
+        assert_eq!(counts(&mut player, 7), [1, 0, 2, 0, 0, 1, 0]);
+        assert_eq!(player.elapsed(), 7);
+        player.restart();
+        assert_eq!(player.elapsed(), 0);
+        match player.due_events()[..] {
+            [Event::KeyPressed { code: Key::Right, .. }] => {}
+            ref events => panic!("right pressed expected, found {:?}", events),
+        }
+    }
+
+    #[test]
+    fn seek_skips_the_previous_events() {
+        let mut player = ReplayPlayer::new(&replay());
+        player.seek(3);
+        assert_eq!(player.elapsed(), 3);
+        assert_eq!(counts(&mut player, 3), [0, 0, 1]);
+        player.seek(2);
+        assert_eq!(counts(&mut player, 1), [2]);
+        player.seek(10);
+        assert_eq!(counts(&mut player, 1), [0]);
+    }
+
+    #[test]
+    fn recorder_counts_the_ticks() {
+        let mut recorder = ReplayRecorder::new();
+        recorder.restart(&[String::from("Maurice")], "arena1");
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        recorder.record(&Event::KeyPressed { code: Key::A, alt, ctrl, shift, system });
+        recorder.tick();
+        recorder.tick();
+        recorder.record(&Event::KeyReleased { code: Key::A, alt, ctrl, shift, system });
+        recorder.record(&Event::Closed);
+        let ticks: Vec<(u32, bool)> = recorder.replay.events.iter().map(|x| (x.tick, x.pressed)).collect();
+        assert_eq!(ticks, [(0, true), (2, false)]);
+        assert_eq!(recorder.replay.level, "arena1");
+    }
+}
@@ -0,0 +1,36 @@
+use crate::assets::Assets;
+use crate::configuration::error::ConfigurationError;
+use crate::configuration::loader;
+use crate::fighters::fighter::FighterSnapshot;
+use crate::training::TrainingSnapshot;
+use serde_derive::{Deserialize, Serialize};
+use sfml::system::Vector2f;
+use std::fs::File;
+use std::io;
+
+/// File the save states are written to and read from, relative to the current directory.
+pub const SAVE_STATE_FILE: &str = "savestate.json";
+
+/// Complete state of a fight at a tick, to go back to it instantly.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FightSnapshot {
+    pub level: String,
+    /// ticks run since the fight started
+    pub tick: u64,
+    /// center of the game view
+    pub camera: Vector2f,
+    pub fighters: Vec<FighterSnapshot>,
+    pub training: Option<TrainingSnapshot>,
+}
+
+impl FightSnapshot {
+    /// Reads a save state file, in json, ron or toml, relative to the current directory.
+    pub fn load(path: &str) -> Result<FightSnapshot, ConfigurationError> {
+        loader::read(&Assets::current_directory(), path)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+}
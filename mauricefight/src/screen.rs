@@ -0,0 +1,187 @@
+use crate::configuration::settings::Settings;
+use serde_derive::{Deserialize, Serialize};
+use sfml::{
+    graphics::{FloatRect, RenderTarget, RenderWindow, View},
+    system::{Vector2f, Vector2u},
+    window::{ContextSettings, Style, VideoMode},
+    SfBox,
+};
+
+/// Logical size of the game : everything is drawn in this coordinate space,
+/// then scaled to the window.
+pub const GAME_WIDTH: f32 = 296.;
+pub const GAME_HEIGHT: f32 = 222.;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScalingMode {
+    /// Largest whole multiple of the game size, keeps pixels sharp.
+    Integer,
+    /// Largest size keeping the aspect ratio.
+    Smooth,
+}
+
+pub struct ScreenConfiguration {
+    pub game_size: Vector2f,
+    pub aa_level: u32,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub frame_limit: u32,
+    pub scaling: ScalingMode,
+}
+
+impl ScreenConfiguration {
+    pub fn new(settings: &Settings) -> Self {
+        ScreenConfiguration {
+            game_size: Vector2f::new(GAME_WIDTH, GAME_HEIGHT),
+            aa_level: settings.aa_level,
+            width: settings.width,
+            height: settings.height,
+            fullscreen: settings.fullscreen,
+            vsync: settings.vsync,
+            frame_limit: settings.frame_limit,
+            scaling: settings.scaling,
+        }
+    }
+}
+
+/// Computes the viewport (in window fractions) displaying the game centered in the window,
+/// the remaining space being left to the letterbox/pillarbox bars.
+pub fn letterbox_viewport(window_size: Vector2u, game_size: Vector2f, scaling: ScalingMode) -> FloatRect {
+    let window_width = window_size.x.max(1) as f32;
+    let window_height = window_size.y.max(1) as f32;
+    let fit = (window_width / game_size.x).min(window_height / game_size.y);
+    let scale = match scaling {
+        ScalingMode::Integer if fit >= 1. => fit.floor(),
+        _ => fit,
+    };
+    let width = game_size.x * scale / window_width;
+    let height = game_size.y * scale / window_height;
+    FloatRect::new((1. - width) / 2., (1. - height) / 2., width, height)
+}
+
+/// Game window rendering the fixed logical game size at any window size.
+pub struct Screen {
+    pub window: RenderWindow,
+    pub view: SfBox<View>,
+    /// fixed view used to draw menus and overlays
+    pub gui_view: SfBox<View>,
+    pub configuration: ScreenConfiguration,
+}
+
+impl Screen {
+    pub fn new(configuration: ScreenConfiguration) -> Self {
+        let window = Screen::create_window(&configuration);
+        let game_size = configuration.game_size;
+        let mut screen = Screen {
+            window,
+            view: View::new(game_size / 2., game_size),
+            gui_view: View::new(game_size / 2., game_size),
+            configuration,
+        };
+        screen.update_viewport();
+        screen
+    }
+
+    fn create_window(configuration: &ScreenConfiguration) -> RenderWindow {
+        let context_settings = ContextSettings {
+            antialiasing_level: configuration.aa_level,
+            ..Default::default()
+        };
+        let (mode, style) = if configuration.fullscreen {
+            (VideoMode::desktop_mode(), Style::FULLSCREEN)
+        } else {
+            (
+                VideoMode::new(configuration.width, configuration.height, 32),
+                Style::DEFAULT,
+            )
+        };
+        let mut window = RenderWindow::new(mode, "Maurice 2D", style, &context_settings);
+        window.set_framerate_limit(configuration.frame_limit);
+        window.set_vertical_sync_enabled(configuration.vsync);
+        window
+    }
+
+    /// Recomputes the letterboxed viewport for the current window size.
+    pub fn update_viewport(&mut self) {
+        let viewport = letterbox_viewport(
+            self.window.size(),
+            self.configuration.game_size,
+            self.configuration.scaling,
+        );
+        self.view.set_viewport(viewport);
+        self.gui_view.set_viewport(viewport);
+        self.window.set_view(&self.view);
+    }
+
+    pub fn on_resize(&mut self, width: u32, height: u32) {
+        if !self.configuration.fullscreen {
+            self.configuration.width = width;
+            self.configuration.height = height;
+        }
+        self.update_viewport();
+    }
+
+    /// Switches between windowed and fullscreen mode by recreating the window.
+    pub fn toggle_fullscreen(&mut self) {
+        self.configuration.fullscreen = !self.configuration.fullscreen;
+        self.window = Screen::create_window(&self.configuration);
+        self.update_viewport();
+    }
+
+    pub fn set_scaling(&mut self, scaling: ScalingMode) {
+        self.configuration.scaling = scaling;
+        self.update_viewport();
+    }
+
+    /// Moves the camera back to the origin of the game space.
+    pub fn reset_view(&mut self) {
+        self.view.set_center(self.configuration.game_size / 2.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_size() -> Vector2f {
+        Vector2f::new(GAME_WIDTH, GAME_HEIGHT)
+    }
+
+    fn assert_viewport(viewport: FloatRect, expected: (f32, f32, f32, f32)) {
+        let found = (viewport.left, viewport.top, viewport.width, viewport.height);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(
+            close(found.0, expected.0) && close(found.1, expected.1) && close(found.2, expected.2) && close(found.3, expected.3),
+            "{:?} instead of {:?}",
+            found,
+            expected
+        );
+    }
+
+    #[test]
+    fn whole_multiple_fills_the_window() {
+        let viewport = letterbox_viewport(Vector2u::new(592, 444), game_size(), ScalingMode::Integer);
+        assert_viewport(viewport, (0., 0., 1., 1.));
+    }
+
+    #[test]
+    fn integer_scaling_keeps_whole_multiples() {
+        let viewport = letterbox_viewport(Vector2u::new(800, 500), game_size(), ScalingMode::Integer);
+        let (width, height) = (592. / 800., 444. / 500.);
+        assert_viewport(viewport, ((1. - width) / 2., (1. - height) / 2., width, height));
+        // smaller than the game, the window is filled keeping the ratio
+        let viewport = letterbox_viewport(Vector2u::new(148, 222), game_size(), ScalingMode::Integer);
+        assert_viewport(viewport, (0., 0.25, 1., 0.5));
+    }
+
+    #[test]
+    fn smooth_scaling_adds_pillarbox_bars() {
+        let viewport = letterbox_viewport(Vector2u::new(888, 444), game_size(), ScalingMode::Smooth);
+        assert_viewport(viewport, (1. / 6., 0., 2. / 3., 1.));
+        let empty = letterbox_viewport(Vector2u::new(0, 0), game_size(), ScalingMode::Smooth);
+        assert!(empty.width <= 1. && empty.height <= 1.);
+    }
+}
@@ -1,37 +1,77 @@
 use crate::common::Direction;
+use crate::configuration::configuration::{SheetFrame, SpriteConfiguration};
 use crate::configuration::resources::GameResources;
-use sfml::graphics::{IntRect, RenderTarget, RenderWindow, Sprite, Transformable};
+use crate::sprites::textured_sprite::TexturedSprite;
+use sfml::graphics::{IntRect, RenderTarget, RenderWindow};
 use sfml::system::Vector2f;
-use sfml::system::{Clock, Vector2};
-use sfml::SfBox;
+use crate::time_control::TICK_DURATION;
+use serde_derive::{Deserialize, Serialize};
 
-pub struct SpriteInfo<'a> {
-    pub sprite: Sprite<'a>,
-    pub size: i32,
+pub struct SpriteInfo {
+    pub sprite: TexturedSprite,
+    pub frames: Vec<SheetFrame>,
     pub index: i32,
     pub rect: IntRect,
 }
 
+impl SpriteInfo {
+    fn frame(&self, step: i32) -> Option<&SheetFrame> {
+        usize::try_from(self.index + step)
+            .ok()
+            .and_then(|index| self.frames.get(index))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AnimationInfo {
-    pub clock: SfBox<Clock>,
+    /// milliseconds spent on the current step, advanced by simulation ticks
+    pub time: f32,
     pub delay: i32,
     pub nb_frames: i32,
     pub step: i32,
     pub started: bool,
+    /// per frame durations, 0 when `delay` applies
+    pub durations: Vec<i32>,
+    /// sheet frame shown at each step from the sprite index, the step itself when missing
+    pub steps: Vec<i32>,
+    /// step reached since the last call to `take_entered_step`
+    #[serde(skip)]
+    entered: Option<i32>,
+}
+
+/// Sheet frame shown at a step, from the sprite index.
+fn frame_offset(steps: &[i32], step: i32) -> i32 {
+    steps.get(step as usize).copied().unwrap_or(step)
 }
 
 impl AnimationInfo {
-    pub fn reset(&mut self, delay: i32, nb_frames: i32) {
+    pub fn reset(&mut self, delay: i32, nb_frames: i32, steps: Vec<i32>, durations: Vec<i32>) {
         self.started = false;
         self.delay = delay;
         self.nb_frames = nb_frames;
+        self.steps = steps;
+        self.durations = durations;
         self.step = 0;
+        self.entered = None;
+    }
+
+    /// Step entered by the last frames, to raise its events once.
+    pub fn take_entered_step(&mut self) -> Option<i32> {
+        self.entered.take()
+    }
+
+    fn current_delay(&self) -> i32 {
+        match self.durations.get(self.step as usize) {
+            Some(duration) if *duration > 0 => *duration,
+            _ => self.delay,
+        }
     }
 
     pub fn next_frame(&mut self) -> bool {
         let mut is_end_animation = false;
         match self.started {
             true => {
+                self.time += TICK_DURATION;
                 if self.is_animation_tick() {
                     is_end_animation = self.is_end_sequence();
                     if !is_end_animation {
@@ -47,20 +87,22 @@ impl AnimationInfo {
     }
 
     fn is_animation_tick(&self) -> bool {
-        self.clock.elapsed_time().as_milliseconds() >= self.delay
+        self.time >= self.current_delay() as f32
     }
 
     fn next_step(&mut self) -> i32 {
-        self.step = self.step + 1;
-        //println!("next step {}", self.step);
-        self.clock.restart();
+        self.step += 1;
+        log::trace!("step {}/{}", self.step, self.nb_frames);
+        self.entered = Some(self.step);
+        self.time = 0.;
         self.step
     }
 
     fn start(&mut self) -> i32 {
         self.started = true;
         self.step = 0;
-        self.clock.restart();
+        self.entered = Some(0);
+        self.time = 0.;
         self.step
     }
 
@@ -69,6 +111,7 @@ impl AnimationInfo {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NavInfo {
     pub position: Vector2f,
     pub speed: Vector2f,
@@ -78,98 +121,149 @@ pub struct DisplayInfo {
     pub scale: Vector2f,
 }
 
-pub struct AnimatedSprite<'a> {
-    pub sprite: SpriteInfo<'a>,
+pub struct AnimatedSprite {
+    pub sprite: SpriteInfo,
     pub display: DisplayInfo,
     pub animation: AnimationInfo,
     pub nav: NavInfo,
 }
 
-impl<'a> AnimatedSprite<'a> {
+impl AnimatedSprite {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        resources: &'a GameResources,
+        resources: &GameResources,
         sprite: &str,
-        size: i32,
+        sheet: &SpriteConfiguration,
         scale: f32,
         x: f32,
         y: f32,
         x_speed: f32,
         y_speed: f32,
-        direction: Direction,
+        _direction: Direction,
         sprite_index: i32,
         delay: i32,
         nb_frames: i32,
     ) -> Self {
-        let mut _sprite = resources.get_sprite(sprite);
-        _sprite.set_origin(Vector2f::new(size as f32 / 2., size as f32 / 2.));
-        AnimatedSprite {
+        let _sprite = resources.get_sprite(sprite);
+        let mut animated_sprite = AnimatedSprite {
             nav: NavInfo {
                 position: Vector2f::new(x, y),
                 speed: Vector2f::new(x_speed, y_speed),
             },
             sprite: SpriteInfo {
                 sprite: _sprite,
+                frames: sheet.sheet_frames(),
                 index: sprite_index,
-                rect: IntRect::new(sprite_index * size, 0, size, size),
-                size,
+                rect: IntRect::default(),
             },
             animation: AnimationInfo {
-                clock: Clock::start(),
+                time: 0.,
                 delay,
                 nb_frames,
                 started: false,
                 step: 0,
+                durations: Vec::new(),
+                steps: Vec::new(),
+                entered: None,
             },
             display: DisplayInfo {
                 scale: Vector2f::new(scale, scale),
             },
+        };
+        animated_sprite.animation.durations = animated_sprite.frame_durations(nb_frames, &[], &[]);
+        animated_sprite.update_texture_rect();
+        animated_sprite
+    }
+
+    /// Durations of the steps : the given ones, then the sheet ones.
+    fn frame_durations(&self, nb_frames: i32, steps: &[i32], durations: &[i32]) -> Vec<i32> {
+        (0..nb_frames)
+            .map(|step| match durations.get(step as usize) {
+                Some(duration) if *duration > 0 => *duration,
+                _ => self
+                    .sprite
+                    .frame(frame_offset(steps, step))
+                    .map_or(0, |frame| frame.duration),
+            })
+            .collect()
+    }
+
+    /// Shows the frame of the current step, its pivot on the sprite position.
+    fn update_texture_rect(&mut self) {
+        let offset = frame_offset(&self.animation.steps, self.animation.step);
+        if let Some(frame) = self.sprite.frame(offset).copied() {
+            self.sprite.rect = IntRect::new(frame.left, frame.top, frame.width, frame.height);
+            self.sprite.sprite.set_texture_rect(self.sprite.rect);
+            self.sprite
+                .sprite
+                .set_origin(Vector2f::new(frame.x_origin, frame.y_origin));
         }
     }
 
+    /// Sheet frame shown by the current step.
+    pub fn current_frame(&self) -> i32 {
+        self.sprite.index + frame_offset(&self.animation.steps, self.animation.step)
+    }
+
+    /// Starts the animation again from its first step, whose events are raised again.
     pub fn restart_animation(&mut self) {
-        self.animation.step = 0;
+        self.animation.start();
+        self.update_texture_rect();
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn reset_animation(
         &mut self,
         sprite_index: i32,
         delay: i32,
         nb_frames: i32,
+        steps: Vec<i32>,
+        durations: &[i32],
         direction: Direction,
         speed: f32,
     ) {
         self.nav.speed.x = if direction == Direction::Right {
             speed
         } else {
-            -1. * speed
+            -speed
         };
         self.sprite.index = sprite_index;
-        self.animation.reset(delay, nb_frames);
+        let durations = self.frame_durations(nb_frames, &steps, durations);
+        self.animation.reset(delay, nb_frames, steps, durations);
     }
 
-    pub fn next_frame(&mut self, direction: Direction, window: &mut RenderWindow) -> (bool, i32) {
-        //println!(
-        //    "STEP {} / INDEX : {} / SIZE : {} / SPEED : {}",
-        //    self.animation.step, self.sprite.index, self.sprite.size, self.nav.speed.x
-        //);
+    /// Advances the animation and the position by one tick.
+    pub fn next_frame(&mut self, direction: Direction) -> (bool, i32) {
         let is_closed_current_action = self.animation.next_frame();
-        self.sprite.rect.left = (self.sprite.index + self.animation.step) * self.sprite.size;
-        //println!(
-        //    "POINT {}",
-        //    self.sprite.rect.left
-        //);
-        self.sprite.sprite.set_texture_rect(self.sprite.rect);
+        self.update_texture_rect();
         self.update_position(direction);
-        window.draw(&self.sprite.sprite);
         (is_closed_current_action, self.animation.step)
     }
 
+    /// Draws the current frame.
+    pub fn draw(&self, window: &mut RenderWindow) {
+        window.draw(&self.sprite.sprite);
+    }
+
+    /// Puts back a saved animation and position, the sprite showing them at once.
+    pub fn restore(&mut self, sprite_index: i32, animation: &AnimationInfo, nav: &NavInfo, direction: Direction) {
+        self.sprite.index = sprite_index;
+        self.animation = animation.clone();
+        self.nav = nav.clone();
+        self.update_texture_rect();
+        self.place(direction);
+    }
+
     fn update_position(&mut self, direction: Direction) {
-        self.nav.position.x = self.nav.position.x + self.nav.speed.x;
-        self.nav.position.y = self.nav.position.y + self.nav.speed.y;
+        self.nav.position += self.nav.speed;
+        self.place(direction);
+    }
+
+    /// Moves the sprite to the position, facing `direction`.
+    fn place(&mut self, direction: Direction) {
         self.sprite.sprite.set_position(self.nav.position);
         let x_scale = if direction == Direction::Left {
-            -1. * self.display.scale.x
+            -self.display.scale.x
         } else {
             self.display.scale.x
         };
@@ -0,0 +1,68 @@
+use crate::configuration::resources::TextureHandle;
+use sfml::graphics::{Color, Drawable, IntRect, RenderStates, RenderTarget, Sprite, Transformable};
+use sfml::system::Vector2f;
+
+/// Sprite sharing its texture with the resource store. The texture is only
+/// borrowed while drawing, so it can be swapped or reloaded at any time.
+#[derive(Clone)]
+pub struct TexturedSprite {
+    texture: TextureHandle,
+    /// area of the texture shown, the whole texture when None
+    rect: Option<IntRect>,
+    position: Vector2f,
+    origin: Vector2f,
+    scale: Vector2f,
+    color: Color,
+}
+
+impl TexturedSprite {
+    pub fn new(texture: TextureHandle) -> Self {
+        TexturedSprite {
+            texture,
+            rect: None,
+            position: Vector2f::new(0., 0.),
+            origin: Vector2f::new(0., 0.),
+            scale: Vector2f::new(1., 1.),
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn set_texture_rect(&mut self, rect: IntRect) {
+        self.rect = Some(rect);
+    }
+
+    pub fn set_position(&mut self, position: Vector2f) {
+        self.position = position;
+    }
+
+    pub fn set_origin(&mut self, origin: Vector2f) {
+        self.origin = origin;
+    }
+
+    pub fn set_scale(&mut self, scale: Vector2f) {
+        self.scale = scale;
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+}
+
+impl Drawable for TexturedSprite {
+    fn draw<'a: 'shader, 'texture, 'shader, 'shader_texture>(
+        &'a self,
+        target: &mut dyn RenderTarget,
+        states: &RenderStates<'texture, 'shader, 'shader_texture>,
+    ) {
+        let texture = self.texture.borrow();
+        let mut sprite = Sprite::with_texture(&texture);
+        if let Some(rect) = self.rect {
+            sprite.set_texture_rect(rect);
+        }
+        sprite.set_position(self.position);
+        sprite.set_origin(self.origin);
+        sprite.set_scale(self.scale);
+        sprite.set_color(self.color);
+        target.draw_with_renderstates(&sprite, states);
+    }
+}
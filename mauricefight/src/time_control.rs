@@ -0,0 +1,153 @@
+/// Duration of a simulation tick in milliseconds, the fight being updated 60 times per second.
+pub const TICK_DURATION: f32 = 1000. / 60.;
+
+/// Speeds cycled by the debug hotkeys, from the normal one.
+const SPEEDS: [f32; 4] = [1., 0.5, 0.25, 0.125];
+/// Longest frame time counted, so that a stall doesn't run the fight in fast forward.
+const MAX_FRAME_TIME: f32 = 250.;
+
+/// Number of simulation ticks run for each frame drawn, from the time the frame
+/// took : 60 per second at normal speed whatever the frame rate, fewer in slow
+/// motion, none when frozen unless a step is requested.
+pub struct TimeControl {
+    speed: f32,
+    frozen: bool,
+    /// ticks requested while frozen
+    steps: u32,
+    /// simulated milliseconds not run yet, carried to the next frames
+    accumulator: f32,
+    /// ticks run since the fight started
+    tick: u64,
+}
+
+impl TimeControl {
+    /// `speed` is the initial one, 1 for the normal speed.
+    pub fn new(speed: f32) -> Self {
+        TimeControl {
+            speed,
+            frozen: false,
+            steps: 0,
+            accumulator: 0.,
+            tick: 0,
+        }
+    }
+
+    /// Counts the ticks from 0, for a new fight.
+    pub fn restart(&mut self) {
+        self.tick = 0;
+        self.accumulator = 0.;
+        self.steps = 0;
+    }
+
+    /// Ticks run since the fight started.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    /// Ticks to run before drawing the next frame, `frame_time` being the
+    /// milliseconds since the previous frame.
+    pub fn ticks(&mut self, frame_time: f32) -> u32 {
+        let ticks = if self.frozen {
+            std::mem::take(&mut self.steps)
+        } else {
+            self.accumulator += frame_time.min(MAX_FRAME_TIME) * self.speed;
+            let ticks = (self.accumulator / TICK_DURATION).floor();
+            self.accumulator -= ticks * TICK_DURATION;
+            ticks as u32
+        };
+        self.tick += ticks as u64;
+        ticks
+    }
+
+    pub fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        self.steps = 0;
+        log::debug!("simulation {}", if self.frozen { "frozen" } else { "resumed" });
+    }
+
+    /// Runs exactly one tick, freezing the simulation if it isn't.
+    pub fn step(&mut self) {
+        if !self.frozen {
+            self.toggle_freeze();
+        }
+        self.steps += 1;
+    }
+
+    /// Next speed of the list, slower or faster. A custom speed goes to the nearest one.
+    pub fn change_speed(&mut self, slower: bool) {
+        let next = if slower {
+            SPEEDS.iter().find(|x| **x < self.speed)
+        } else {
+            SPEEDS.iter().rev().find(|x| **x > self.speed)
+        };
+        if let Some(speed) = next {
+            self.speed = *speed;
+            log::debug!("simulation speed {}", self.speed);
+        }
+    }
+
+    /// Speed, state and tick count, for the debug overlay.
+    pub fn describe(&self) -> String {
+        let speed = if self.frozen {
+            String::from("gel")
+        } else if self.speed < 1. && (1. / self.speed).fract() == 0. {
+            format!("1/{}", 1. / self.speed)
+        } else {
+            format!("x{}", self.speed)
+        };
+        format!("vitesse {}\nticks {}", speed, self.tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_60_ticks_per_second_whatever_the_frame_rate() {
+        let mut time_control = TimeControl::new(1.);
+        assert_eq!(time_control.ticks(TICK_DURATION * 3.5), 3);
+        assert_eq!(time_control.ticks(TICK_DURATION * 0.6), 1);
+        assert_eq!(time_control.ticks(TICK_DURATION * 0.2), 0);
+        assert_eq!(time_control.tick(), 4);
+        time_control.restart();
+        assert_eq!(time_control.tick(), 0);
+        assert_eq!(time_control.ticks(TICK_DURATION * 0.9), 0);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut time_control = TimeControl::new(1.);
+        assert_eq!(time_control.ticks(10_000.), (MAX_FRAME_TIME / TICK_DURATION) as u32);
+    }
+
+    #[test]
+    fn frozen_simulation_runs_the_requested_steps() {
+        let mut time_control = TimeControl::new(1.);
+        time_control.step();
+        time_control.step();
+        assert_eq!(time_control.ticks(TICK_DURATION * 10.), 2);
+        assert_eq!(time_control.ticks(TICK_DURATION * 10.), 0);
+        assert!(time_control.describe().starts_with("vitesse gel"));
+        time_control.toggle_freeze();
+        assert_eq!(time_control.ticks(TICK_DURATION * 1.5), 1);
+    }
+
+    #[test]
+    fn speed_slows_the_ticks() {
+        let mut time_control = TimeControl::new(1.);
+        time_control.change_speed(true);
+        assert_eq!(time_control.ticks(TICK_DURATION * 4.5), 2);
+        assert!(time_control.describe().starts_with("vitesse 1/2"));
+        time_control.change_speed(false);
+        time_control.change_speed(false);
+        assert!(time_control.describe().starts_with("vitesse x1"));
+        let mut custom = TimeControl::new(0.3);
+        custom.change_speed(true);
+        assert!(custom.describe().starts_with("vitesse 1/4"));
+    }
+}
@@ -0,0 +1,547 @@
+use crate::common::InputProcessor;
+use crate::configuration::configuration::{ActionConfiguration, AnimationEvent, Configuration};
+use crate::fighters::fighter::Fighter;
+use crate::fighters::fighter_state::State;
+use crate::gui::debug;
+use crate::gui::text::{Align, BitmapFont};
+use crate::replay::{Replay, ReplayPlayer, ReplayRecorder};
+use crate::screen::GAME_HEIGHT;
+use crate::time_control::TICK_DURATION;
+use serde_derive::{Deserialize, Serialize};
+use sfml::graphics::{Color, RenderWindow};
+use sfml::system::Vector2f;
+use sfml::window::{Event, Key};
+
+/// Recording slots of the dummy inputs, selected with the 1 to 3 keys.
+const SLOT_KEYS: [Key; 3] = [Key::Num1, Key::Num2, Key::Num3];
+/// Longest recording of the dummy inputs, 10 seconds in ticks.
+const MAX_RECORDING: u32 = 600;
+
+/// What the dummy does in the training mode.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DummyBehaviour {
+    Stand,
+    Crouch,
+    /// blocks every hit
+    Block,
+    /// jumps again as soon as it lands
+    Jump,
+    /// blocks one hit out of two on average
+    RandomBlock,
+}
+
+impl DummyBehaviour {
+    pub const ALL: [DummyBehaviour; 5] = [
+        DummyBehaviour::Stand,
+        DummyBehaviour::Crouch,
+        DummyBehaviour::Block,
+        DummyBehaviour::Jump,
+        DummyBehaviour::RandomBlock,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DummyBehaviour::Stand => "debout",
+            DummyBehaviour::Crouch => "accroupi",
+            DummyBehaviour::Block => "garde",
+            DummyBehaviour::Jump => "saut",
+            DummyBehaviour::RandomBlock => "garde aléatoire",
+        }
+    }
+
+    /// Decides whether the dummy blocks the hit touching it.
+    fn blocks(self, rng: &mut u64) -> bool {
+        match self {
+            DummyBehaviour::Block => true,
+            DummyBehaviour::RandomBlock => next_random(rng) & 1 == 1,
+            _ => false,
+        }
+    }
+}
+
+/// Next value of a xorshift generator, its state being saved with the fight.
+fn next_random(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *state = x;
+    x
+}
+
+/// Ticks lasted by `duration` milliseconds.
+fn to_frames(duration: i32) -> i32 {
+    (duration as f32 / TICK_DURATION).round() as i32
+}
+
+/// Frame data of an attack, computed from the step durations of its sequence
+/// and its `hitbox_start` and `hitbox_end` events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameData {
+    pub action: String,
+    /// frames until the first active one, included
+    pub startup: i32,
+    pub active: i32,
+    pub recovery: i32,
+    pub damage: i32,
+    /// frame advantage when hitting on the first active frame
+    pub on_hit: i32,
+    pub on_block: i32,
+}
+
+impl FrameData {
+    /// None for an action without hitbox.
+    pub fn from_action(action: &ActionConfiguration) -> Option<Self> {
+        let sequence = &action.sequence;
+        let nb_steps = sequence.nb_frames.max(0) as usize;
+        let frames = &sequence.frames[..nb_steps.min(sequence.frames.len())];
+        let step_of = |event: AnimationEvent| frames.iter().position(|frame| frame.events.contains(&event));
+        let start = step_of(AnimationEvent::HitboxStart)?;
+        let end = step_of(AnimationEvent::HitboxEnd).filter(|end| *end > start).unwrap_or(nb_steps);
+        // time at which each step is reached, then the end of the sequence
+        let mut times = vec![0];
+        for step in 0..nb_steps {
+            let duration = frames.get(step).map_or(0, |frame| frame.duration);
+            let duration = if duration > 0 { duration } else { sequence.delay };
+            times.push(times[step] + duration);
+        }
+        let frame_at = |step: usize| to_frames(times[step]);
+        let active = frame_at(end) - frame_at(start);
+        let recovery = frame_at(nb_steps) - frame_at(end);
+        // the attacker still has the other active frames and the recovery to play
+        let busy = active - 1 + recovery;
+        Some(FrameData {
+            action: action.name.clone(),
+            startup: frame_at(start) + 1,
+            active,
+            recovery,
+            damage: action.damage,
+            on_hit: action.hitstun - busy,
+            on_block: action.blockstun - busy,
+        })
+    }
+}
+
+/// Result of the last attack of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Outcome {
+    Hit,
+    Blocked,
+}
+
+/// State of the training at a tick, the recordings excepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingSnapshot {
+    player_state: State,
+    last_move: Option<FrameData>,
+    outcome: Option<Outcome>,
+    damage: i32,
+    rng: u64,
+    /// recording played to the dummy
+    #[serde(default)]
+    playback: Option<PlaybackPosition>,
+}
+
+/// Slot played to the dummy and the ticks already played.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PlaybackPosition {
+    slot: usize,
+    tick: u32,
+    looped: bool,
+}
+
+/// Inputs recorded for the dummy.
+struct DummyRecording {
+    replay: Replay,
+    /// ticks, the playback loops after them
+    duration: u32,
+}
+
+/// Who controls the dummy.
+enum DummyControl {
+    /// the dummy behaviour of the settings
+    Behaviour,
+    /// the keyboard, instead of the player fighter
+    Player,
+    /// the keyboard, the inputs being recorded to the current slot
+    Recording(ReplayRecorder),
+    /// the recording of the current slot
+    Playback { player: ReplayPlayer, looped: bool },
+}
+
+fn key_event(code: Key, pressed: bool) -> Event {
+    let (alt, ctrl, shift, system) = (false, false, false, false);
+    if pressed {
+        Event::KeyPressed { code, alt, ctrl, shift, system }
+    } else {
+        Event::KeyReleased { code, alt, ctrl, shift, system }
+    }
+}
+
+/// Duration of a number of ticks, in seconds.
+fn seconds(ticks: u32) -> f32 {
+    ticks as f32 * TICK_DURATION / 1000.
+}
+
+fn signed(value: i32) -> String {
+    format!("{:+}", value)
+}
+
+/// Training mode : the first fighter is the player, the second one a dummy with
+/// infinite health. Shows the frame data of the last attack of the player.
+pub struct Training {
+    /// state of the player at the previous frame, to detect new attacks
+    player_state: State,
+    last_move: Option<FrameData>,
+    /// None while the last attack hasn't touched the dummy
+    outcome: Option<Outcome>,
+    /// damage dealt to the dummy since the last reset
+    damage: i32,
+    /// state of the random generator of the dummy, never 0
+    rng: u64,
+    control: DummyControl,
+    slots: Vec<Option<DummyRecording>>,
+    /// index of the current slot
+    slot: usize,
+}
+
+impl Training {
+    pub fn new() -> Self {
+        Training {
+            player_state: State::Idle,
+            last_move: None,
+            outcome: None,
+            damage: 0,
+            rng: rand::random::<u64>() | 1,
+            control: DummyControl::Behaviour,
+            slots: SLOT_KEYS.iter().map(|_| None).collect(),
+            slot: 0,
+        }
+    }
+
+    pub fn snapshot(&self) -> TrainingSnapshot {
+        TrainingSnapshot {
+            player_state: self.player_state,
+            last_move: self.last_move.clone(),
+            outcome: self.outcome,
+            damage: self.damage,
+            rng: self.rng,
+            playback: match &self.control {
+                DummyControl::Playback { player, looped } => Some(PlaybackPosition {
+                    slot: self.slot,
+                    tick: player.elapsed(),
+                    looped: *looped,
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    /// Goes back to a snapshot, the dummy playing its recording from the same tick
+    /// if the slot still holds one. The keyboard keeps the dummy it controls.
+    pub fn restore(&mut self, snapshot: &TrainingSnapshot) {
+        self.player_state = snapshot.player_state;
+        self.last_move = snapshot.last_move.clone();
+        self.outcome = snapshot.outcome;
+        self.damage = snapshot.damage;
+        self.rng = snapshot.rng.max(1);
+        if self.controls_dummy() {
+            return;
+        }
+        let playback = snapshot
+            .playback
+            .and_then(|x| self.slots.get(x.slot)?.as_ref().map(|recording| (x, recording)));
+        self.control = match playback {
+            Some((position, recording)) => {
+                let mut player = ReplayPlayer::new(&recording.replay);
+                player.seek(position.tick);
+                self.slot = position.slot;
+                DummyControl::Playback { player, looped: position.looped }
+            }
+            None => DummyControl::Behaviour,
+        };
+    }
+
+    /// The keyboard controls the dummy instead of the player fighter.
+    pub fn controls_dummy(&self) -> bool {
+        matches!(self.control, DummyControl::Player | DummyControl::Recording(_))
+    }
+
+    /// Forwards a keyboard event to the dummy, recording it if needed.
+    pub fn process_dummy_event(&mut self, e: Event, dummy: &mut Fighter) {
+        if let DummyControl::Recording(recorder) = &mut self.control {
+            recorder.record(&e);
+        }
+        dummy.process_event(e);
+    }
+
+    /// Hotkeys of the dummy control, returns true when the event is handled.
+    /// F6 takes the control of the dummy, F7 starts and stops a recording,
+    /// F8 plays the current slot once and F9 in a loop.
+    pub fn process_event(&mut self, e: Event, dummy: &mut Fighter) -> bool {
+        let code = match e {
+            Event::KeyPressed { code, .. } => code,
+            _ => return false,
+        };
+        match code {
+            Key::F6 => {
+                self.stop_recording();
+                let control = if self.controls_dummy() { DummyControl::Behaviour } else { DummyControl::Player };
+                self.set_control(control, dummy);
+            }
+            Key::F7 => {
+                if matches!(self.control, DummyControl::Recording(_)) {
+                    self.stop_recording();
+                } else {
+                    log::debug!("training : recording slot {}", self.slot + 1);
+                    self.set_control(DummyControl::Recording(ReplayRecorder::new()), dummy);
+                }
+            }
+            Key::F8 | Key::F9 => {
+                let looped = code == Key::F9;
+                match (&self.control, &self.slots[self.slot]) {
+                    (DummyControl::Playback { looped: playing, .. }, _) if *playing == looped => {
+                        self.set_control(DummyControl::Behaviour, dummy);
+                    }
+                    (_, Some(recording)) => {
+                        let player = ReplayPlayer::new(&recording.replay);
+                        self.set_control(DummyControl::Playback { player, looped }, dummy);
+                    }
+                    (_, None) => log::info!("training : slot {} is empty", self.slot + 1),
+                }
+            }
+            _ => match SLOT_KEYS.iter().position(|x| *x == code) {
+                Some(slot) => {
+                    self.stop_recording();
+                    if matches!(self.control, DummyControl::Playback { .. }) {
+                        self.set_control(DummyControl::Behaviour, dummy);
+                    }
+                    self.slot = slot;
+                }
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Changes who controls the dummy, releasing the directions it holds.
+    fn set_control(&mut self, control: DummyControl, dummy: &mut Fighter) {
+        dummy.release_inputs();
+        self.control = control;
+    }
+
+    /// Saves the recording in progress to the current slot, the keyboard keeps the dummy.
+    fn stop_recording(&mut self) {
+        if let DummyControl::Recording(recorder) = &self.control {
+            let duration = recorder.elapsed().min(MAX_RECORDING);
+            log::debug!(
+                "training : {} input(s) recorded in slot {}",
+                recorder.replay.events.len(),
+                self.slot + 1
+            );
+            self.slots[self.slot] = Some(DummyRecording {
+                replay: recorder.replay.clone(),
+                duration,
+            });
+            self.control = DummyControl::Player;
+        }
+    }
+
+    /// Plays the inputs due of the current slot, and stops recordings that are too long.
+    fn update_control(&mut self, dummy: &mut Fighter, behaviour: DummyBehaviour) {
+        match &mut self.control {
+            DummyControl::Behaviour => {
+                let crouch = behaviour == DummyBehaviour::Crouch;
+                if dummy.is_crouching() != crouch {
+                    dummy.process_event(key_event(Key::Down, crouch));
+                }
+                if behaviour == DummyBehaviour::Jump {
+                    dummy.jump();
+                }
+            }
+            DummyControl::Player => {}
+            DummyControl::Recording(recorder) => {
+                recorder.tick();
+                if recorder.elapsed() >= MAX_RECORDING {
+                    self.stop_recording();
+                }
+            }
+            DummyControl::Playback { player, looped } => {
+                for event in player.due_events() {
+                    dummy.process_event(event);
+                }
+                let duration = self.slots[self.slot].as_ref().map_or(0, |x| x.duration);
+                if player.elapsed() >= duration {
+                    dummy.release_inputs();
+                    if *looped {
+                        player.restart();
+                    } else {
+                        self.control = DummyControl::Behaviour;
+                    }
+                }
+            }
+        }
+    }
+
+    fn control_text(&self, behaviour: DummyBehaviour) -> String {
+        let slot = match &self.slots[self.slot] {
+            Some(recording) => format!("{:.1} s", seconds(recording.duration)),
+            None => String::from("vide"),
+        };
+        let control = match &self.control {
+            DummyControl::Behaviour => format!("{} (f5)", behaviour.label()),
+            DummyControl::Player => String::from("joueur (f6)"),
+            DummyControl::Recording(recorder) => {
+                format!("enregistrement {:.1} s (f7)", seconds(recorder.elapsed()))
+            }
+            DummyControl::Playback { looped: false, .. } => String::from("lecture (f8)"),
+            DummyControl::Playback { looped: true, .. } => String::from("lecture en boucle (f9)"),
+        };
+        format!("mannequin : {}\nemplacement {} : {}", control, self.slot + 1, slot)
+    }
+
+    /// Forgets the damage dealt, when the positions are reset.
+    pub fn reset(&mut self) {
+        self.damage = 0;
+        self.outcome = None;
+    }
+
+    /// Controls the dummy and follows the attacks of the player, once the fighters are updated.
+    pub fn update(
+        &mut self,
+        fighters: &mut [Fighter],
+        behaviour: DummyBehaviour,
+        configuration: &Configuration,
+    ) {
+        let (player, dummy) = match fighters {
+            [player, dummy, ..] => (player, dummy),
+            _ => return,
+        };
+        self.update_control(dummy, behaviour);
+
+        let state = player.current_state();
+        if state != self.player_state {
+            self.player_state = state;
+            let action = configuration
+                .get_character(player.get_name())
+                .and_then(|x| x.get_action_configuration(&state.to_string()));
+            if let Some(frame_data) = action.and_then(FrameData::from_action) {
+                log::debug!("training : {:?}", frame_data);
+                self.last_move = Some(frame_data);
+                self.outcome = None;
+            }
+        }
+        let frame_data = match &self.last_move {
+            Some(frame_data) if self.outcome.is_none() && player.touches(dummy) => frame_data,
+            _ => return,
+        };
+        player.register_touch();
+        let blocked = behaviour.blocks(&mut self.rng);
+        // the dummy has infinite health, the damage is counted here
+        dummy.take_hit(0, blocked);
+        if blocked {
+            self.outcome = Some(Outcome::Blocked);
+        } else {
+            self.outcome = Some(Outcome::Hit);
+            self.damage += frame_data.damage;
+        }
+    }
+
+    /// Draws the dummy settings and the frame data readout, in the gui view.
+    pub fn draw(&self, window: &mut RenderWindow, font: &mut BitmapFont, behaviour: DummyBehaviour) {
+        let mut lines = vec![
+            self.control_text(behaviour),
+            format!("dégâts : {} (f4 : replacer)", self.damage),
+        ];
+        if let Some(frame_data) = &self.last_move {
+            let outcome = match self.outcome {
+                Some(Outcome::Hit) => "touché",
+                Some(Outcome::Blocked) => "gardé",
+                None => "raté",
+            };
+            lines.push(format!("{} : {}", frame_data.action, outcome));
+            lines.push(format!(
+                "démarrage {} actif {} récupération {}",
+                frame_data.startup, frame_data.active, frame_data.recovery
+            ));
+            lines.push(format!(
+                "dégâts {} touché {} gardé {}",
+                frame_data.damage,
+                signed(frame_data.on_hit),
+                signed(frame_data.on_block)
+            ));
+        }
+        let text = lines.join("\n");
+        font.set_scale(debug::TEXT_SCALE);
+        let position = Vector2f::new(2., GAME_HEIGHT - 2. - font.line_height() * lines.len() as f32);
+        debug::draw_text(window, font, &text, position, Align::Left, Color::WHITE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::configuration::{FrameConfiguration, SpriteSequenceConfiguration};
+
+    /// Attack of 4 steps of 50 ms, 3 ticks each, active on the second and third steps.
+    fn attack() -> ActionConfiguration {
+        let frame = |count, events: Vec<AnimationEvent>| FrameConfiguration {
+            count,
+            events,
+            ..FrameConfiguration::default()
+        };
+        ActionConfiguration {
+            name: String::from("punch"),
+            sequence: SpriteSequenceConfiguration {
+                index: 0,
+                nb_frames: 4,
+                delay: 50,
+                speed: 0.,
+                frames: vec![
+                    frame(0, Vec::new()),
+                    frame(1, vec![AnimationEvent::HitboxStart]),
+                    frame(2, Vec::new()),
+                    frame(3, vec![AnimationEvent::HitboxEnd]),
+                ],
+            },
+            sound: None,
+            damage: 8,
+            hitstun: 20,
+            blockstun: 5,
+        }
+    }
+
+    #[test]
+    fn frame_data_counts_the_ticks_of_the_steps() {
+        let frame_data = FrameData::from_action(&attack()).unwrap();
+        assert_eq!(frame_data.action, "punch");
+        assert_eq!((frame_data.startup, frame_data.active, frame_data.recovery), (4, 6, 3));
+        assert_eq!(frame_data.damage, 8);
+        // 5 active frames and 3 of recovery left after the first active one
+        assert_eq!((frame_data.on_hit, frame_data.on_block), (12, -3));
+    }
+
+    #[test]
+    fn frame_data_uses_the_frame_durations() {
+        let mut action = attack();
+        action.sequence.frames[2].duration = 100;
+        let frame_data = FrameData::from_action(&action).unwrap();
+        assert_eq!((frame_data.startup, frame_data.active, frame_data.recovery), (4, 9, 3));
+    }
+
+    #[test]
+    fn frame_data_active_until_the_end_without_hitbox_end() {
+        let mut action = attack();
+        action.sequence.frames[3].events.clear();
+        let frame_data = FrameData::from_action(&action).unwrap();
+        assert_eq!((frame_data.active, frame_data.recovery), (9, 0));
+    }
+
+    #[test]
+    fn no_frame_data_without_hitbox() {
+        let mut action = attack();
+        action.sequence.frames[1].events.clear();
+        assert!(FrameData::from_action(&action).is_none());
+    }
+}